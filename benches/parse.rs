@@ -0,0 +1,70 @@
+use std::fmt::Write;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use lisparser::{
+    lisp_comb::{lisp_object, parse_forms},
+    parse,
+    parser_comb::{any, count_matches, one_of, take_while},
+    Parser,
+};
+
+/// Builds `count` top-level definitions mixing every kind of atom, about
+/// 100 bytes each.
+fn generate(count: usize) -> String {
+    let mut source = String::new();
+    for i in 0..count {
+        writeln!(
+            source,
+            "(define (fn-{i} x y) ; comment\n  (if (< x {i}) \"str {i}\" '(#\\a {i}.5 :key #t #(x y) (a . b))))"
+        )
+        .unwrap();
+    }
+    source
+}
+
+/// Full parses are dominated by failed alternatives rather than by reading
+/// chars, so changes to the char primitives show up in [`bench_primitives`]
+/// rather than here.
+fn bench_parse(c: &mut Criterion) {
+    let source = generate(5_000);
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+
+    group.bench_function("parse_forms", |b| b.iter(|| parse_forms(&source).unwrap()));
+
+    let list = format!("({source})");
+    group.bench_function("lisp_object", |b| {
+        b.iter_batched(
+            lisp_object,
+            |parser| parse(parser, &list).unwrap(),
+            BatchSize::SmallInput,
+        );
+    });
+    group.finish();
+}
+
+fn bench_primitives(c: &mut Criterion) {
+    let source = generate(5_000);
+    let mut group = c.benchmark_group("primitives");
+    group.throughput(Throughput::Bytes(source.len() as u64));
+
+    group.bench_function("any", |b| {
+        b.iter(|| count_matches(any()).parse(&source).unwrap());
+    });
+    group.bench_function("one_of", |b| {
+        b.iter(|| {
+            count_matches(one_of(LETTERS).or_same(any()))
+                .parse(&source)
+                .unwrap()
+        });
+    });
+    group.bench_function("take_while", |b| {
+        b.iter(|| take_while(|c| c != '\u{0}').parse(&source).unwrap());
+    });
+    group.finish();
+}
+
+const LETTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+
+criterion_group!(benches, bench_parse, bench_primitives);
+criterion_main!(benches);
@@ -1,12 +1,623 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 pub mod lisp_comb;
 pub mod parser_comb;
 pub use parser_comb::{parse, Parser};
 
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::{
+    fmt::{self, Write},
+    iter,
+    str::FromStr,
+};
+
+use lisp_comb::lisp_object;
+use parser_comb::{Error, Span};
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value", rename_all = "lowercase")
+)]
 pub enum LispObject {
     List(Vec<LispObject>),
     String(String),
     Ident(String),
+    Number(i32),
+    /// Integer too large for [`LispObject::Number`].
+    #[cfg(feature = "bignum")]
+    BigInt(num_bigint::BigInt),
+    /// Rational number in lowest terms, written `3/4`. The denominator is
+    /// always positive.
+    Ratio(i64, i64),
+    Float(f64),
+    Char(char),
+    Bool(bool),
+    Keyword(String),
+    /// Cons cell written as `(a . b)`. `(a b . c)` is `(a . (b . c))`.
+    Pair(Box<LispObject>, Box<LispObject>),
+    Vector(Vec<LispObject>),
+}
+
+impl LispObject {
+    /// Returns the elements if this is a [`LispObject::List`]. Vectors and
+    /// dotted pairs are not lists.
+    #[must_use]
+    pub fn as_list(&self) -> Option<&[LispObject]> {
+        match self {
+            Self::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_ident(&self) -> Option<&str> {
+        match self {
+            Self::Ident(ident) => Some(ident),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn is_list(&self) -> bool {
+        matches!(self, Self::List(_))
+    }
+
+    #[must_use]
+    pub fn is_ident(&self) -> bool {
+        matches!(self, Self::Ident(_))
+    }
+
+    #[must_use]
+    pub fn is_string(&self) -> bool {
+        matches!(self, Self::String(_))
+    }
+
+    /// Calls `f` on this object and then on each of its children, in order.
+    pub fn walk<F: FnMut(&LispObject)>(&self, mut f: F) {
+        let mut stack = vec![self];
+        while let Some(obj) = stack.pop() {
+            f(obj);
+            match obj {
+                Self::List(items) | Self::Vector(items) => stack.extend(items.iter().rev()),
+                Self::Pair(head, tail) => stack.extend([&**tail, &**head]),
+                _ => {}
+            }
+        }
+    }
+
+    /// Like [`walk`](Self::walk), but lets `f` edit the objects in place.
+    ///
+    /// Children are visited after `f` has run on their parent, so `f` sees
+    /// the children it put there.
+    pub fn walk_mut<F: FnMut(&mut LispObject)>(&mut self, mut f: F) {
+        let mut stack = vec![self];
+        while let Some(obj) = stack.pop() {
+            f(obj);
+            match obj {
+                Self::List(items) | Self::Vector(items) => stack.extend(items.iter_mut().rev()),
+                Self::Pair(head, tail) => stack.extend([&mut **tail, &mut **head]),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Iterates over the elements of a [`LispObject::List`]. Anything else,
+/// including vectors, has no elements.
+impl<'a> IntoIterator for &'a LispObject {
+    type Item = &'a LispObject;
+    type IntoIter = core::slice::Iter<'a, LispObject>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_list().unwrap_or_default().iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut LispObject {
+    type Item = &'a mut LispObject;
+    type IntoIter = core::slice::IterMut<'a, LispObject>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            LispObject::List(items) => items.iter_mut(),
+            _ => <&mut [LispObject]>::default().iter_mut(),
+        }
+    }
+}
+
+/// Line width [`LispObject::pretty`] tries to stay within.
+const PRETTY_WIDTH: usize = 80;
+
+impl LispObject {
+    /// Formats the object across multiple lines, indenting nested lists by
+    /// `indent` spaces per level.
+    ///
+    /// Lists that fit in the remaining width stay on one line, written as by
+    /// [`Display`](fmt::Display). Longer ones put each element after the
+    /// first on its own line.
+    #[must_use]
+    pub fn pretty(&self, indent: usize) -> String {
+        let mut out = String::new();
+        self.write_pretty(&self.widths(), &mut out, indent, 0, 0);
+        out
+    }
+
+    /// Measures the one-line width of every list and vector, bottom-up, so
+    /// [`LispObject::write_pretty`] formats each subtree at most once.
+    fn widths(&self) -> Widths {
+        let (open, items) = match self {
+            Self::List(items) => ("(", items),
+            Self::Vector(items) => ("#(", items),
+            _ => {
+                let mut width = CharCount(0);
+                // Counting chars can't fail.
+                let _ = write!(width, "{self}");
+                return Widths {
+                    compact: width.0,
+                    items: vec![],
+                };
+            }
+        };
+        let items: Vec<_> = items.iter().map(Self::widths).collect();
+        let spaces = items.len().saturating_sub(1);
+        Widths {
+            compact: open.len() + items.iter().map(|item| item.compact).sum::<usize>() + spaces + 1,
+            items,
+        }
+    }
+
+    /// Writes the object starting at `column`, and returns the column it ends
+    /// at.
+    fn write_pretty(
+        &self,
+        widths: &Widths,
+        out: &mut String,
+        indent: usize,
+        level: usize,
+        column: usize,
+    ) -> usize {
+        let fits = column + widths.compact <= PRETTY_WIDTH;
+        let (open, items) = match self {
+            Self::List(items) if !fits => ("(", items),
+            Self::Vector(items) if !fits => ("#(", items),
+            _ => {
+                // Writing to a `String` can't fail.
+                let _ = write!(out, "{self}");
+                return column + widths.compact;
+            }
+        };
+
+        out.push_str(open);
+        let mut column = column + open.len();
+        for (i, (item, width)) in items.iter().zip(&widths.items).enumerate() {
+            if i > 0 {
+                column = (level + 1) * indent;
+                out.push('\n');
+                out.extend(iter::repeat_n(' ', column));
+            }
+            column = item.write_pretty(width, out, indent, level + 1, column);
+        }
+        out.push(')');
+        column + 1
+    }
+}
+
+/// One-line widths in chars of an object and of the elements of a list or
+/// vector, computed by [`LispObject::widths`].
+struct Widths {
+    compact: usize,
+    items: Vec<Widths>,
+}
+
+/// [`fmt::Write`] sink that only counts the chars written to it.
+struct CharCount(usize);
+
+impl fmt::Write for CharCount {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "json")]
+impl LispObject {
+    /// Converts this object into JSON.
+    ///
+    /// Lists and vectors become arrays, and numbers, floats and bools map to
+    /// their JSON counterparts, with non-finite floats as `null`. Strings,
+    /// idents and chars all become JSON strings, so `foo` and `"foo"` convert
+    /// to the same value; keywords keep their `:` to stay apart from idents.
+    /// A pair `(a . b)` becomes `{"car": a, "cdr": b}`. Big integers and
+    /// ratios become strings, as in `"3/4"`.
+    ///
+    /// Use the `serde` feature for a lossless encoding instead.
+    #[must_use]
+    pub fn to_json(&self) -> serde_json::Value {
+        use alloc::string::ToString;
+        use serde_json::Value;
+
+        match self {
+            Self::List(items) | Self::Vector(items) => {
+                Value::Array(items.iter().map(Self::to_json).collect())
+            }
+            Self::String(s) | Self::Ident(s) => Value::String(s.clone()),
+            Self::Number(n) => Value::from(*n),
+            #[cfg(feature = "bignum")]
+            Self::BigInt(n) => Value::String(n.to_string()),
+            Self::Ratio(..) => Value::String(self.to_string()),
+            Self::Float(n) => Value::from(*n),
+            Self::Char(c) => Value::String((*c).into()),
+            Self::Bool(b) => Value::Bool(*b),
+            Self::Keyword(s) => Value::String(alloc::format!(":{s}")),
+            Self::Pair(head, tail) => serde_json::json!({
+                "car": head.to_json(),
+                "cdr": tail.to_json(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for LispObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::List(list) => {
+                f.write_str("(")?;
+                write_elements(f, list)?;
+                f.write_str(")")
+            }
+            Self::Vector(vector) => {
+                f.write_str("#(")?;
+                write_elements(f, vector)?;
+                f.write_str(")")
+            }
+            Self::String(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        '\r' => f.write_str("\\r")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Self::Ident(ident) => f.write_str(ident),
+            Self::Number(n) => write!(f, "{n}"),
+            #[cfg(feature = "bignum")]
+            Self::BigInt(n) => write!(f, "{n}"),
+            Self::Ratio(numerator, denominator) => write!(f, "{numerator}/{denominator}"),
+            // Written as in Scheme, since `NaN` and `inf` would be read back
+            // as identifiers.
+            Self::Float(n) if n.is_nan() => f.write_str("+nan.0"),
+            Self::Float(n) if n.is_infinite() => f.write_str(if n.is_sign_positive() {
+                "+inf.0"
+            } else {
+                "-inf.0"
+            }),
+            // `Debug` always keeps a fractional part or an exponent, so the
+            // output is read back as a float rather than a number.
+            Self::Float(n) => write!(f, "{n:?}"),
+            Self::Char(' ') => f.write_str("#\\space"),
+            Self::Char('\n') => f.write_str("#\\newline"),
+            Self::Char('\t') => f.write_str("#\\tab"),
+            Self::Char(c) => write!(f, "#\\{c}"),
+            Self::Bool(true) => f.write_str("#t"),
+            Self::Bool(false) => f.write_str("#f"),
+            Self::Keyword(name) => write!(f, ":{name}"),
+            Self::Pair(head, tail) => {
+                write!(f, "({head}")?;
+                let mut tail = tail;
+                while let Self::Pair(head, rest) = &**tail {
+                    write!(f, " {head}")?;
+                    tail = rest;
+                }
+                write!(f, " . {tail})")
+            }
+        }
+    }
+}
+
+fn write_elements(f: &mut fmt::Formatter<'_>, elements: &[LispObject]) -> fmt::Result {
+    for (i, obj) in elements.iter().enumerate() {
+        if i > 0 {
+            f.write_str(" ")?;
+        }
+        write!(f, "{obj}")?;
+    }
+    Ok(())
+}
+
+impl FromStr for LispObject {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(lisp_object(), s)
+    }
+}
+
+impl TryFrom<&str> for LispObject {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for LispObject {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// [`LispObject`] that also records the [`Span`] of every node, as parsed by
+/// [`lisp_comb::lisp_object_spanned`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedLispObject {
+    pub node: SpannedNode,
+    pub span: Span,
+}
+
+/// Node of a [`SpannedLispObject`], mirroring [`LispObject`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedNode {
+    List(Vec<SpannedLispObject>),
+    String(String),
+    Ident(String),
+    Number(i32),
+    #[cfg(feature = "bignum")]
+    BigInt(num_bigint::BigInt),
+    Ratio(i64, i64),
+    Float(f64),
+    Char(char),
+    Bool(bool),
+    Keyword(String),
+    Pair(Box<SpannedLispObject>, Box<SpannedLispObject>),
+    Vector(Vec<SpannedLispObject>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        use LispObject::*;
+
+        let obj = List(vec![
+            Ident("print".into()),
+            String("say \"hi\"\\\n".into()),
+            Number(-1),
+            Ratio(-3, 4),
+            Float(2.0),
+            Char('x'),
+            Char(' '),
+            Bool(true),
+            Keyword("key".into()),
+            Pair(Box::new(Number(1)), Box::new(Number(2))),
+            Vector(vec![Number(3), Number(4)]),
+            List(vec![]),
+        ]);
+        assert_eq!(
+            obj.to_string(),
+            r#"(print "say \"hi\"\\\n" -1 -3/4 2.0 #\x #\space #t :key (1 . 2) #(3 4) ())"#
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let input = r#"(define (greet name) (print "hello, \"" name "\"\\") (f 1 -2.5e-3 #\a #\newline #f :k (a b . c) #(x)))"#;
+        let parsed = parse(lisp_object(), input).unwrap();
+        let formatted = parsed.to_string();
+        assert_eq!(parse(lisp_object(), &formatted), Ok(parsed));
+
+        let obj = LispObject::List(vec![
+            LispObject::Float(f64::INFINITY),
+            LispObject::Float(f64::NEG_INFINITY),
+        ]);
+        assert_eq!(obj.to_string(), "(+inf.0 -inf.0)");
+        assert_eq!(obj.to_string().parse(), Ok(obj));
+
+        let nan = LispObject::Float(f64::NAN).to_string();
+        assert_eq!(nan, "+nan.0");
+        assert!(matches!(nan.parse(), Ok(LispObject::Float(n)) if n.is_nan()));
+    }
+
+    #[test]
+    fn test_from_str() {
+        use LispObject::*;
+
+        let obj: LispObject = "(a \"b\")".parse().unwrap();
+        assert_eq!(obj, List(vec![Ident("a".into()), String("b".into())]));
+
+        let err = "(a) junk".parse::<LispObject>().unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+
+        assert!("".parse::<LispObject>().is_err());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let list: LispObject = "(f \"s\")".parse().unwrap();
+        let items = list.as_list().unwrap();
+        let (ident, string) = (&items[0], &items[1]);
+        let vector: LispObject = "#(f)".parse().unwrap();
+
+        assert_eq!(items.len(), 2);
+        assert!(list.is_list());
+        assert_eq!(ident.as_list(), None);
+        assert_eq!(vector.as_list(), None);
+        assert!(!vector.is_list());
+
+        assert_eq!(ident.as_ident(), Some("f"));
+        assert!(ident.is_ident());
+        assert_eq!(string.as_ident(), None);
+        assert!(!list.is_ident());
+
+        assert_eq!(string.as_string(), Some("s"));
+        assert!(string.is_string());
+        assert_eq!(ident.as_string(), None);
+        assert!(!ident.is_string());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let obj: LispObject = "(a b c)".parse().unwrap();
+        let mut idents = vec![];
+        for child in &obj {
+            idents.extend(child.as_ident());
+        }
+        assert_eq!(idents, ["a", "b", "c"]);
+
+        let atom = LispObject::Number(1);
+        assert_eq!((&atom).into_iter().count(), 0);
+        let vector: LispObject = "#(a)".parse().unwrap();
+        assert_eq!((&vector).into_iter().count(), 0);
+
+        let mut obj = obj;
+        for child in &mut obj {
+            if let LispObject::Ident(name) = child {
+                name.make_ascii_uppercase();
+            }
+        }
+        assert_eq!(obj, "(A B C)".parse().unwrap());
+        let mut atom = atom;
+        assert_eq!((&mut atom).into_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_try_from() {
+        use LispObject::*;
+
+        fn convert<T: TryInto<LispObject, Error = Error>>(value: T) -> Result<LispObject, Error> {
+            value.try_into()
+        }
+
+        let expected = List(vec![Ident("a".into()), Number(1)]);
+        assert_eq!(LispObject::try_from("(a 1)"), Ok(expected.clone()));
+        assert_eq!(
+            LispObject::try_from("(a 1)".to_owned()),
+            Ok(expected.clone())
+        );
+        assert_eq!(convert("(a 1)"), Ok(expected));
+
+        let err = LispObject::try_from("(a 1").unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+        let err = LispObject::try_from("(a) b".to_owned()).unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+        assert!(convert("".to_owned()).is_err());
+    }
+
+    #[test]
+    fn test_walk() {
+        let obj: LispObject = "(a (b) c)".parse().unwrap();
+        let mut idents = vec![];
+        obj.walk(|obj| {
+            if let LispObject::Ident(name) = obj {
+                idents.push(name.clone());
+            }
+        });
+        assert_eq!(idents, ["a", "b", "c"]);
+
+        let mut obj: LispObject = "(a (b . c) #(d))".parse().unwrap();
+        obj.walk_mut(|obj| {
+            if let LispObject::Ident(name) = obj {
+                name.make_ascii_uppercase();
+            }
+        });
+        assert_eq!(obj, "(A (B . C) #(D))".parse().unwrap());
+    }
+
+    #[test]
+    fn test_pretty() {
+        let obj: LispObject = r#"(define (fibonacci n) (if (less n 2) n (add (fibonacci (sub n 1)) (fibonacci (sub n 2)))) "tab\t")"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            obj.pretty(2),
+            r#"(define
+  (fibonacci n)
+  (if (less n 2) n (add (fibonacci (sub n 1)) (fibonacci (sub n 2))))
+  "tab\t")"#
+        );
+        assert_eq!(obj.pretty(2).parse(), Ok(obj));
+
+        let short: LispObject = "(a #(b c))".parse().unwrap();
+        assert_eq!(short.pretty(4), "(a #(b c))");
+
+        let wide = format!("(a \"{}\")", "é".repeat(70));
+        let obj: LispObject = wide.parse().unwrap();
+        assert_eq!(obj.pretty(2), wide);
+
+        let inner = format!("#({})", ["abcdefghij"; 7].join(" "));
+        let obj: LispObject = format!("#({inner} b)").parse().unwrap();
+        assert_eq!(
+            obj.pretty(1),
+            "#(#(abcdefghij\n  abcdefghij\n  abcdefghij\n  abcdefghij\n  abcdefghij\n  abcdefghij\n  abcdefghij)\n b)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_to_json() {
+        let obj: LispObject = r#"(define (f x) "x" #(1 2.5) #\c #t :k (a . b))"#.parse().unwrap();
+        assert_eq!(
+            obj.to_json(),
+            serde_json::json!([
+                "define",
+                ["f", "x"],
+                "x",
+                [1, 2.5],
+                "c",
+                true,
+                ":k",
+                { "car": "a", "cdr": "b" },
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_round_trip() {
+        use LispObject::*;
+
+        let obj: LispObject = r#"(a "b" 1 (2.5))"#.parse().unwrap();
+        let json = serde_json::to_value(&obj).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "list",
+                "value": [
+                    { "type": "ident", "value": "a" },
+                    { "type": "string", "value": "b" },
+                    { "type": "number", "value": 1 },
+                    { "type": "list", "value": [{ "type": "float", "value": 2.5 }] },
+                ]
+            })
+        );
+
+        let back: LispObject = serde_json::from_value(json).unwrap();
+        assert_eq!(back, obj);
+        assert_eq!(
+            serde_json::from_str::<LispObject>(r#"{"type":"ident","value":"x"}"#).unwrap(),
+            Ident("x".into())
+        );
+    }
 }
@@ -1,115 +1,1509 @@
+use alloc::{borrow::Cow, boxed::Box, string::String, vec, vec::Vec};
+use core::str::FromStr;
+
 use crate::{
-    parser_comb::{any, character, from_fn, many, range, whitespace, Error, Get, Parser},
-    LispObject,
+    parser_comb::{
+        any, character, delimited, from_fn, many, many1, not, one_of, parse, range, satisfy,
+        skip_many, tag, take_while, take_while1, whitespace, Error, Parser, Span,
+    },
+    LispObject, SpannedLispObject, SpannedNode,
 };
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn string() -> impl Parser<Output = String> {
-    character('"')
-        .flat_map(|_| any().until(character('"')))
-        .zip_left(character('"'))
-        .map(|s| s.into_iter().collect())
-}
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn string() -> impl Parser<Output = String> {
+    let mut escape = escape();
+    let string_char = from_fn(move |input: &str| {
+        if input.starts_with('\\') {
+            escape.parse(input)
+        } else {
+            any().parse(input)
+        }
+    });
+
+    delimited(
+        character('"'),
+        string_char.until(character('"')),
+        character('"'),
+    )
+    .map(|s| s.into_iter().collect())
+}
+
+/// Like [`string`], but borrows the contents from `input` when there are no
+/// escapes to process.
+///
+/// # Errors
+///
+/// This function will return an error if `input` does not start with a string.
+pub fn string_ref(input: &str) -> Result<(Cow<'_, str>, &str), Error> {
+    let body = input.strip_prefix('"').ok_or_else(|| Error::new(input))?;
+    match body.find(['"', '\\']) {
+        Some(end) if body[end..].starts_with('"') => {
+            Ok((Cow::Borrowed(&body[..end]), &body[end + 1..]))
+        }
+        _ => string()
+            .parse(input)
+            .map(|(parsed, rest)| (Cow::Owned(parsed), rest)),
+    }
+}
+
+/// Raw string like `#"C:\dir"#`, taken as written without processing
+/// escapes. It may span lines, and more `#`s can surround it when the text
+/// contains `"#`, as in `##"a "#quoted"# word"##`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn raw_string() -> impl Parser<Output = String> {
+    from_fn(|input: &str| {
+        let body = input.trim_start_matches('#');
+        let hashes = input.len() - body.len();
+        let body = match body.strip_prefix('"') {
+            Some(body) if hashes > 0 => body,
+            _ => return Err(Error::new(input)),
+        };
+
+        let mut close = String::from('"');
+        close.push_str(&input[..hashes]);
+        let end = body.find(&close).ok_or_else(|| Error::new(input))?;
+        Ok((body[..end].into(), &body[end + close.len()..]))
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+fn escape() -> impl Parser<Output = char> {
+    character('\\').zip_right(from_fn(|input| {
+        let (c, rest) = any().parse(input)?;
+        let escaped = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '\\' => '\\',
+            '"' => '"',
+            'u' => return unicode_escape().parse(rest),
+            _ => return Err(Error::new(input)),
+        };
+        Ok((escaped, rest))
+    }))
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+fn unicode_escape() -> impl Parser<Output = char> {
+    from_fn(|input| {
+        let mut digits = character('{')
+            .zip_right(many1(one_of("0123456789abcdefABCDEF")))
+            .zip_left(character('}'));
+
+        let (digits, rest) = digits.parse(input)?;
+        u32::from_str_radix(&digits.into_iter().collect::<String>(), 16)
+            .ok()
+            .and_then(char::from_u32)
+            .map_or(Err(Error::new(input)), |c| Ok((c, rest)))
+    })
+}
+
+/// Punctuation allowed anywhere in an identifier, besides `_`. `+` and `-`
+/// are allowed too, but only start one when no digit follows.
+const SYMBOL_CHARS: &str = "*/?!<>=";
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn ident() -> impl Parser<Output = String> {
+    ident_with(true)
+}
+
+/// [`ident`], optionally without the [`SYMBOL_CHARS`] and signs.
+fn ident_with(symbols: bool) -> impl Parser<Output = String> {
+    let (symbols, signs) = if symbols {
+        (SYMBOL_CHARS, "+-")
+    } else {
+        ("", "")
+    };
+    from_fn(move |input| {
+        // A sign followed by a digit starts a number instead.
+        let mut first = satisfy(|c| c == '_' || c.is_ascii_alphabetic() || symbols.contains(c))
+            .or_same(one_of(signs).zip_left(not(range('0'..='9'))));
+        let mut other = take_while(|c| {
+            c == '_' || c.is_ascii_alphanumeric() || symbols.contains(c) || signs.contains(c)
+        });
+
+        let (first_char, rest) = first.parse(input)?;
+        let (parsed, rest) = other.parse(rest)?;
+
+        let mut ident = String::from(first_char);
+        ident.push_str(&parsed);
+        Ok((ident, rest))
+    })
+}
+
+/// Like [`ident`], but returns the [`Span`] of the identifier instead of a
+/// new `String`. [`Span::slice`] gives the identifier as a slice of the input.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn ident_ref() -> impl Parser<Output = Span> {
+    ident().recognize()
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn keyword() -> impl Parser<Output = String> {
+    character(':').zip_right(ident())
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn number() -> impl Parser<Output = i32> {
+    integer()
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn number_i64() -> impl Parser<Output = i64> {
+    integer()
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn number_u64() -> impl Parser<Output = u64> {
+    integer()
+}
+
+/// Rational number like `-6/8`, as a numerator and a positive denominator in
+/// lowest terms.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn ratio() -> impl Parser<Output = (i64, i64)> {
+    ratio_from(signed_digits())
+}
+
+/// [`ratio`] with the numerator scanned by `signed`, see [`signed_digits`].
+fn ratio_from<P>(mut signed: P) -> impl Parser<Output = (i64, i64)>
+where
+    P: Parser<Output = Span>,
+{
+    from_fn(move |input| {
+        let (numerator, rest) = signed.parse(input)?;
+        let numerator: i64 = parse_digits(numerator.slice(input)).ok_or(Error::new(input))?;
+        let (_, rest) = character('/').parse(rest)?;
+        let (denominator, rest) = digits().parse(rest)?;
+
+        let denominator: i64 = match denominator.parse() {
+            Ok(0) | Err(_) => return Err(Error::new(input)),
+            Ok(denominator) => denominator,
+        };
+        let divisor = gcd(numerator.unsigned_abs(), denominator.unsigned_abs());
+        #[allow(clippy::cast_possible_wrap)] // divides `denominator`, so it fits
+        let divisor = divisor as i64;
+        Ok(((numerator / divisor, denominator / divisor), rest))
+    })
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(feature = "bignum")]
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn bignum() -> impl Parser<Output = num_bigint::BigInt> {
+    integer()
+}
+
+/// Decimal integer with an optional sign, in whatever type it's parsed into.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+fn integer<T: FromStr>() -> impl Parser<Output = T> {
+    integer_from(signed_digits())
+}
+
+/// [`integer`] with the digits scanned by `signed`, see [`signed_digits`].
+fn integer_from<T, P>(mut signed: P) -> impl Parser<Output = T>
+where
+    T: FromStr,
+    P: Parser<Output = Span>,
+{
+    from_fn(move |input| {
+        let (literal, rest) = signed.parse(input)?;
+        match parse_digits(literal.slice(input)) {
+            Some(n) => Ok((n, rest)),
+            None => Err(Error::new(input)),
+        }
+    })
+}
+
+/// The span of an optional sign and [`digits`], which every decimal number
+/// starts with. [`lisp_decimal`] scans it once for all the number parsers.
+fn signed_digits() -> impl Parser<Output = Span> + Clone {
+    (one_of("+-").optional(), digits()).recognize()
+}
+
+/// Parses the text of [`signed_digits`], leaving out the underscores.
+fn parse_digits<T: FromStr>(literal: &str) -> Option<T> {
+    literal.replace('_', "").parse().ok()
+}
+
+/// Decimal digits, optionally grouped by single underscores as in
+/// `1_000_000`. The underscores are left out of the output.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+fn digits() -> impl Parser<Output = String> + Clone {
+    from_fn(|input| {
+        let (parsed, rest) = take_while1(|c| c.is_ascii_digit() || c == '_').parse(input)?;
+        if parsed.starts_with('_') || parsed.ends_with('_') || parsed.contains("__") {
+            return Err(Error::new(input));
+        }
+        Ok((parsed.replace('_', ""), rest))
+    })
+}
+
+/// Hex digits with an optional `0x` or `0X` prefix. The prefix must be
+/// followed by at least one digit.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn hex_number() -> impl Parser<Output = i32> {
+    from_fn(move |input: &str| {
+        let mut digits = take_while1(|c| c.is_ascii_hexdigit());
+
+        let rest = input
+            .strip_prefix("0x")
+            .or_else(|| input.strip_prefix("0X"))
+            .unwrap_or(input);
+        let (parsed, rest) = digits.parse(rest)?;
+        if let Ok(n) = i32::from_str_radix(&parsed, 16) {
+            Ok((n, rest))
+        } else {
+            Err(Error::new(input))
+        }
+    })
+}
+
+/// Integer in Common Lisp radix syntax: `#x1F`, `#o17` or `#b1010`, with an
+/// optional sign after the prefix. Like [`boolean`], the digits run to the
+/// end of the word, so `#b12` is an error rather than `#b1` followed by `2`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn radix_number() -> impl Parser<Output = i32> {
+    from_fn(|input| {
+        let (_, rest) = character('#').parse(input)?;
+        let (prefix, rest) = one_of("xXoObB").parse(rest)?;
+        let (sign, rest) = one_of("+-").optional().parse(rest)?;
+        let (digits, rest) = take_while1(|c| c.is_alphanumeric() || c == '_').parse(rest)?;
+
+        let radix = match prefix.to_ascii_lowercase() {
+            'x' => 16,
+            'o' => 8,
+            _ => 2,
+        };
+        let literal: String = sign.into_iter().chain(digits.chars()).collect();
+        if let Ok(n) = i32::from_str_radix(&literal, radix) {
+            Ok((n, rest))
+        } else {
+            Err(Error::new(input))
+        }
+    })
+}
+
+/// Decimal float like `-2.5e-3`, or one of the non-finite floats `+inf.0`,
+/// `-inf.0` and `+nan.0` as written in Scheme.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn float() -> impl Parser<Output = f64> {
+    non_finite().or_same(float_from(signed_digits()))
+}
+
+/// `+inf.0`, `-inf.0`, `+nan.0` or `-nan.0`.
+fn non_finite() -> impl Parser<Output = f64> {
+    tag("+inf.0")
+        .value(f64::INFINITY)
+        .or_same(tag("-inf.0").value(f64::NEG_INFINITY))
+        .or_same(tag("+nan.0").value(f64::NAN))
+        .or_same(tag("-nan.0").value(f64::NAN))
+}
+
+/// [`float`] with the integer part scanned by `signed`, see
+/// [`signed_digits`].
+fn float_from<P>(mut signed: P) -> impl Parser<Output = f64>
+where
+    P: Parser<Output = Span>,
+{
+    from_fn(move |input| {
+        let mut fraction = character('.').zip_right(digits()).optional();
+        // Unlike the fraction, an exponent must have digits once it's begun.
+        let mut exponent = from_fn(|input| {
+            let Ok((_, rest)) = one_of("eE").parse(input) else {
+                return Ok((None, input));
+            };
+            let (sign, rest) = one_of("+-").optional().parse(rest)?;
+            let (digits, rest) = digits().parse(rest)?;
+            Ok((Some((sign, digits)), rest))
+        });
+
+        let (integer, rest) = signed.parse(input)?;
+        let (fraction, rest) = fraction.parse(rest)?;
+        let (exponent, rest) = exponent.parse(rest)?;
+
+        let mut literal = integer.slice(input).replace('_', "");
+        if let Some(fraction) = fraction {
+            literal.push('.');
+            literal.push_str(&fraction);
+        }
+        if let Some((sign, digits)) = exponent {
+            literal.push('e');
+            literal.extend(sign);
+            literal.push_str(&digits);
+        }
+
+        if let Ok(n) = literal.parse() {
+            Ok((n, rest))
+        } else {
+            Err(Error::new(input))
+        }
+    })
+}
+
+/// Returns the [`Span`] of a numeric literal as written, like `-007` or
+/// `1_000.5e3`. Accepts everything [`float`] does, integers included.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn number_ref() -> impl Parser<Output = Span> {
+    float().recognize()
+}
+
+/// Parses `#t`, `#true`, `#f` or `#false`.
+///
+/// The literal must not be directly followed by an identifier character, so
+/// `#tx` is an error rather than `#t` followed by `x`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn boolean() -> impl Parser<Output = bool> {
+    from_fn(|input| {
+        let (_, rest) = character('#').parse(input)?;
+        let (word, rest) = take_while(|c| c.is_alphanumeric() || c == '_').parse(rest)?;
+        match word.as_str() {
+            "t" | "true" => Ok((true, rest)),
+            "f" | "false" => Ok((false, rest)),
+            _ => Err(Error::new(input)),
+        }
+    })
+}
+
+/// Parses a character literal like `#\a` or `#\space`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn character_literal() -> impl Parser<Output = char> {
+    from_fn(|input| {
+        let (_, rest) = tag("#\\").parse(input)?;
+        let (c, after) = any().parse(rest)?;
+        let (name, after_name) = take_while(char::is_alphabetic).parse(rest)?;
+
+        if name.chars().count() <= 1 {
+            return Ok((c, after));
+        }
+        let named = match name.to_ascii_lowercase().as_str() {
+            "space" => ' ',
+            "newline" => '\n',
+            "tab" => '\t',
+            _ => return Err(Error::new(rest).with_label("a character name")),
+        };
+        Ok((named, after_name))
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn comment() -> impl Parser<Output = ()> {
+    line_comment(';')
+}
+
+/// A comment from `start` to the end of the line, like [`comment`] with
+/// another comment character.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn line_comment(start: char) -> impl Parser<Output = ()> {
+    character(start)
+        .zip_left(take_while(|c| c != '\n'))
+        .map(|_| ())
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn block_comment() -> impl Parser<Output = ()> {
+    from_fn(|input: &str| {
+        let Some(mut rest) = input.strip_prefix("#|") else {
+            return Err(Error::new(input));
+        };
+
+        let mut depth = 1_usize;
+        while depth > 0 {
+            if let Some(r) = rest.strip_prefix("#|") {
+                depth += 1;
+                rest = r;
+            } else if let Some(r) = rest.strip_prefix("|#") {
+                depth -= 1;
+                rest = r;
+            } else {
+                let mut chars = rest.chars();
+                if chars.next().is_none() {
+                    return Err(Error::new(rest));
+                }
+                rest = chars.as_str();
+            }
+        }
+        Ok(((), rest))
+    })
+}
+
+/// A single whitespace character or a comment.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn blank() -> impl Parser<Output = ()> {
+    blank_with(LispConfig::default())
+}
+
+/// [`blank`] with the comment syntax of `config`.
+fn blank_with(config: LispConfig) -> impl Parser<Output = ()> {
+    enabled(config.line_comment.map(line_comment))
+        .or_same(enabled(config.block_comments.then(block_comment)))
+        .or_same(whitespace())
+}
+
+/// Runs `parser` if there is one, and fails otherwise.
+fn enabled<P: Parser>(mut parser: Option<P>) -> impl Parser<Output = P::Output> {
+    from_fn(move |input: &str| match &mut parser {
+        Some(parser) => parser.parse(input),
+        None => Err(Error::new(input)),
+    })
+}
+
+/// Skips any run of [`blank`]s. This is how all Lisp parsers skip whitespace.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn skip_whitespace() -> impl Parser<Output = ()> {
+    skip_many(blank())
+}
+
+/// Runs `parser`, then skips the whitespace and comments after it.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn token<P: Parser>(parser: P) -> impl Parser<Output = P::Output> {
+    parser.zip_left(skip_whitespace())
+}
+
+/// An integer followed by a delimiter, so not the start of a [`float`] or of
+/// a word like `1x`. With the `bignum` feature, integers too large for an
+/// `i32` become [`LispObject::BigInt`]s; without it they are an error.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_number() -> impl Parser<Output = LispObject> {
+    lisp_number_from(signed_digits())
+}
+
+/// [`lisp_number`] with the digits scanned by `signed`, see
+/// [`signed_digits`].
+fn lisp_number_from<P>(signed: P) -> impl Parser<Output = LispObject>
+where
+    P: Parser<Output = Span> + Clone,
+{
+    let number = integer_from(signed.clone()).map(LispObject::Number);
+    #[cfg(feature = "bignum")]
+    let number = number.or_same(integer_from(signed).map(LispObject::BigInt));
+    #[cfg(not(feature = "bignum"))]
+    let number = number.or_same(integer_overflow(signed));
+    number.zip_left(number_end())
+}
+
+/// Fails on an integer literal too large for [`number`], and commits to the
+/// failure so the literal isn't read as a [`float`] instead.
+#[cfg(not(feature = "bignum"))]
+fn integer_overflow<P>(signed: P) -> impl Parser<Output = LispObject>
+where
+    P: Parser<Output = Span>,
+{
+    let mut literal = signed.zip_left(number_end());
+    let mut overflow = from_fn(|input| Err(Error::new(input)))
+        .context("an integer that fits in an i32")
+        .cut();
+    from_fn(move |input| {
+        literal.parse(input)?;
+        overflow.parse(input)
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_radix_number() -> impl Parser<Output = LispObject> {
+    radix_number().map(LispObject::Number)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_ratio() -> impl Parser<Output = LispObject> {
+    ratio()
+        .map(|(numerator, denominator)| LispObject::Ratio(numerator, denominator))
+        .zip_left(number_end())
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_float() -> impl Parser<Output = LispObject> {
+    float().map(LispObject::Float).zip_left(number_end())
+}
+
+/// Chars other than whitespace that end an atom.
+const DELIMITERS: &str = "()[]\";'`,";
+
+/// Whether `c` separates tokens: whitespace or one of the [`DELIMITERS`].
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || DELIMITERS.contains(c)
+}
+
+/// Checks that a number ends here, at a delimiter or the end of the input,
+/// so `1e` or `1/2x` is an error rather than a number and an identifier.
+fn number_end() -> impl Parser<Output = ()> {
+    from_fn(|input: &str| match input.chars().next() {
+        Some(c) if !is_delimiter(c) => {
+            Err(Error::new(input).with_label("a delimiter after the number"))
+        }
+        _ => Ok(((), input)),
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_bool() -> impl Parser<Output = LispObject> {
+    boolean().map(LispObject::Bool)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_char() -> impl Parser<Output = LispObject> {
+    character_literal().map(LispObject::Char)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_string() -> impl Parser<Output = LispObject> {
+    string().map(LispObject::String)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_raw_string() -> impl Parser<Output = LispObject> {
+    raw_string().map(LispObject::String)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_ident() -> impl Parser<Output = LispObject> {
+    ident().map(LispObject::Ident)
+}
+
+/// Like [`lisp_ident`], but folds identifiers to lowercase, so `Foo`, `FOO`
+/// and `foo` all parse to the same `Ident`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_ident_ci() -> impl Parser<Output = LispObject> {
+    ident().map(|ident| LispObject::Ident(ident.to_lowercase()))
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_keyword() -> impl Parser<Output = LispObject> {
+    keyword().map(LispObject::Keyword)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_atom() -> impl Parser<Output = LispObject> {
+    atom_with(LispConfig::default())
+}
+
+/// [`lisp_atom`] with the identifier syntax of `config`.
+fn atom_with(config: LispConfig) -> impl Parser<Output = LispObject> {
+    let config_ident = move || {
+        ident_with(config.symbol_chars).map(move |ident| {
+            if config.fold_case {
+                ident.to_lowercase()
+            } else {
+                ident
+            }
+        })
+    };
+
+    // Only try the `#` syntaxes where they can start, so other atoms don't
+    // pay for all their failures.
+    let hashed = lisp_raw_string()
+        .or_same(lisp_char())
+        .or_same(lisp_bool())
+        .or_same(lisp_radix_number());
+    lisp_string()
+        .or_same(character('#').peek().zip_right(hashed))
+        .or_same(lisp_decimal())
+        .or_same(config_ident().map(LispObject::Ident))
+        .or_same(
+            character(':')
+                .zip_right(config_ident())
+                .map(LispObject::Keyword),
+        )
+}
+
+/// A [`lisp_ratio`], [`lisp_number`] or [`lisp_float`]. The sign and digits
+/// they all start with are only scanned once, and the char after them picks
+/// which one to parse. Without digits, only the non-finite floats are left.
+fn lisp_decimal() -> impl Parser<Output = LispObject> {
+    from_fn(|input| {
+        let Ok((signed, rest)) = signed_digits().parse(input) else {
+            return non_finite()
+                .map(LispObject::Float)
+                .zip_left(number_end())
+                .parse(input);
+        };
+        let scanned = from_fn(move |input: &str| Ok((signed, &input[signed.len()..])));
+        match rest.chars().next() {
+            Some('/') => ratio_from(scanned)
+                .map(|(numerator, denominator)| LispObject::Ratio(numerator, denominator))
+                .zip_left(number_end())
+                .parse(input),
+            Some('.' | 'e' | 'E') => float_from(scanned)
+                .map(LispObject::Float)
+                .zip_left(number_end())
+                .parse(input),
+            _ => lisp_number_from(scanned).parse(input),
+        }
+    })
+}
+
+/// Syntax options for the small differences between Lisp dialects.
+///
+/// The [`Default`] is the syntax of [`lisp_object`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LispConfig {
+    /// Fold identifiers and keywords to lowercase, like [`lisp_ident_ci`].
+    pub fold_case: bool,
+    /// Allow punctuation like `-`, `*` and `?` in identifiers, as [`ident`]
+    /// does. Otherwise only letters, digits and `_` are allowed.
+    pub symbol_chars: bool,
+    /// Accept `[...]` as a list, in addition to `(...)`.
+    pub brackets: bool,
+    /// Character starting a comment that runs to the end of the line.
+    pub line_comment: Option<char>,
+    /// Allow nested `#| ... |#` comments.
+    pub block_comments: bool,
+    /// Nesting limit, as for [`lisp_object_with_max_depth`].
+    pub max_depth: usize,
+}
+
+impl Default for LispConfig {
+    fn default() -> Self {
+        Self {
+            fold_case: false,
+            symbol_chars: true,
+            brackets: false,
+            line_comment: Some(';'),
+            block_comments: true,
+            max_depth: MAX_DEPTH,
+        }
+    }
+}
+
+impl LispConfig {
+    /// Builds a parser for an atom or a list in this dialect.
+    #[must_use = "parsers do nothing unless passed to [`parse`]"]
+    pub fn object_parser(&self) -> impl Parser<Output = LispObject> {
+        tree(*self)
+    }
+}
+
+/// Default nesting limit of [`lisp_object`].
+pub const MAX_DEPTH: usize = 256;
+
+/// Parses an atom or a list nested at most [`MAX_DEPTH`] levels deep.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_object() -> impl Parser<Output = LispObject> {
+    lisp_object_with_max_depth(MAX_DEPTH)
+}
+
+/// Parses a whole document of top-level forms, such as a source file.
+///
+/// # Errors
+///
+/// This function will return an error if anything other than forms,
+/// whitespace and comments is found.
+pub fn parse_forms(input: &str) -> Result<Vec<LispObject>, Error> {
+    let mut skip = skip_whitespace();
+    let mut form = token(lisp_object());
+    let forms = from_fn(move |input| {
+        let (_, mut input) = skip.parse(input)?;
+        let mut forms = vec![];
+        while !input.is_empty() {
+            let (parsed, rest) = form.parse(input)?;
+            forms.push(parsed);
+            input = rest;
+        }
+        Ok((forms, input))
+    });
+    parse(forms, input)
+}
+
+/// Parses an atom or a list nested at most `max_depth` levels deep.
+///
+/// Lists are parsed with an explicit stack instead of recursion, so the
+/// element parsers are only built once per call to this function. The depth
+/// limit bounds the size of the resulting tree, which is dropped recursively.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_object_with_max_depth(max_depth: usize) -> impl Parser<Output = LispObject> {
+    LispConfig {
+        max_depth,
+        ..LispConfig::default()
+    }
+    .object_parser()
+}
+
+/// Like [`lisp_object`], but records the span of every node.
+///
+/// Like all [`Span`]s, they are resolved with [`Span::locate`] against the
+/// whole input, even when this parser only sees part of it. A list's span
+/// covers its parentheses, and the `quote` of `'x` spans the `'`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_object_spanned() -> impl Parser<Output = SpannedLispObject> {
+    lisp_object_spanned_with_max_depth(MAX_DEPTH)
+}
+
+/// Like [`lisp_object_with_max_depth`], but records the span of every node.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_object_spanned_with_max_depth(
+    max_depth: usize,
+) -> impl Parser<Output = SpannedLispObject> {
+    tree(LispConfig {
+        max_depth,
+        ..LispConfig::default()
+    })
+}
+
+/// Tree built by [`tree`], so that spans are only kept when asked for.
+trait Node: Sized {
+    fn atom(obj: LispObject, span: Span) -> Self;
+    fn list(items: Vec<Self>, span: Span) -> Self;
+    fn vector(items: Vec<Self>, span: Span) -> Self;
+    /// Pair spanning from the start of `head` to the end of `close`.
+    fn pair(head: Self, tail: Self, close: Span) -> Self;
+    /// Extends the span back to the start of `start`.
+    fn with_start(self, start: Span) -> Self;
+}
+
+impl Node for LispObject {
+    fn atom(obj: LispObject, _: Span) -> Self {
+        obj
+    }
+
+    fn list(items: Vec<Self>, _: Span) -> Self {
+        Self::List(items)
+    }
+
+    fn vector(items: Vec<Self>, _: Span) -> Self {
+        Self::Vector(items)
+    }
+
+    fn pair(head: Self, tail: Self, _: Span) -> Self {
+        Self::Pair(Box::new(head), Box::new(tail))
+    }
+
+    fn with_start(self, _: Span) -> Self {
+        self
+    }
+}
+
+impl Node for SpannedLispObject {
+    fn atom(obj: LispObject, span: Span) -> Self {
+        let node = match obj {
+            LispObject::String(s) => SpannedNode::String(s),
+            LispObject::Ident(s) => SpannedNode::Ident(s),
+            LispObject::Number(n) => SpannedNode::Number(n),
+            #[cfg(feature = "bignum")]
+            LispObject::BigInt(n) => SpannedNode::BigInt(n),
+            LispObject::Ratio(numerator, denominator) => SpannedNode::Ratio(numerator, denominator),
+            LispObject::Float(n) => SpannedNode::Float(n),
+            LispObject::Char(c) => SpannedNode::Char(c),
+            LispObject::Bool(b) => SpannedNode::Bool(b),
+            LispObject::Keyword(s) => SpannedNode::Keyword(s),
+            LispObject::List(_) | LispObject::Pair(..) | LispObject::Vector(_) => {
+                unreachable!("atoms are not compound")
+            }
+        };
+        Self { node, span }
+    }
+
+    fn list(items: Vec<Self>, span: Span) -> Self {
+        Self {
+            node: SpannedNode::List(items),
+            span,
+        }
+    }
+
+    fn vector(items: Vec<Self>, span: Span) -> Self {
+        Self {
+            node: SpannedNode::Vector(items),
+            span,
+        }
+    }
+
+    fn pair(head: Self, tail: Self, close: Span) -> Self {
+        Self {
+            span: head.span.to(close),
+            node: SpannedNode::Pair(Box::new(head), Box::new(tail)),
+        }
+    }
+
+    fn with_start(mut self, start: Span) -> Self {
+        self.span = start.to(self.span);
+        self
+    }
+}
+
+fn tree<T: Node>(config: LispConfig) -> impl Parser<Output = T> {
+    let skip = move || skip_many(blank_with(config));
+    let mut atom = atom_with(config).spanned();
+    let mut open = one_of(if config.brackets { "([" } else { "(" }).zip_left(skip());
+    let mut open_vector = tag("#(").zip_left(skip());
+    let mut prefix = character('\'')
+        .value("quote")
+        .or_same(character('`').value("quasiquote"))
+        .or_same(tag(",@").value("unquote-splicing"))
+        .or_same(character(',').value("unquote"))
+        .spanned()
+        .zip_left(skip());
+    // The dot of a pair is a token of its own, so `a.b` or `.5` isn't one.
+    let mut dot = character('.')
+        .zip_left(not(satisfy(|c| !is_delimiter(c))))
+        .zip_left(skip());
+    let mut skip = skip();
+
+    from_fn(move |mut input| {
+        let whole = input;
+        let after_delimiter = |rest: &str| {
+            let before = &whole[..whole.len() - rest.len()];
+            before.is_empty() || before.ends_with(is_delimiter)
+        };
+        let mut stack: Vec<(Span, Frame<T>)> = vec![];
+        loop {
+            // Kept to report with the error if no object follows either.
+            let mut unclosed = None;
+            let closed = match stack.last() {
+                Some((_, Frame::List(_, close))) => match character(*close).parse(input) {
+                    Ok(closed) => Some(closed),
+                    Err(e) if input.starts_with([')', ']']) => return Err(e),
+                    Err(e) => {
+                        unclosed = Some(e);
+                        None
+                    }
+                },
+                Some((_, Frame::Vector(_))) => match character(')').parse(input) {
+                    Ok(closed) => Some(closed),
+                    Err(e) if input.starts_with(']') => return Err(e),
+                    Err(e) => {
+                        unclosed = Some(e);
+                        None
+                    }
+                },
+                Some((_, Frame::Dotted(_, close, Some(_)))) => {
+                    Some(character(*close).parse(input)?)
+                }
+                _ => None,
+            };
+
+            let (mut obj, end) = match closed {
+                Some((_, rest)) => {
+                    let close = Span::new(input, rest);
+                    (_, input) = skip.parse(rest)?;
+                    let obj = match stack.pop() {
+                        Some((start, Frame::List(items, _))) => T::list(items, start.to(close)),
+                        Some((start, Frame::Vector(items))) => T::vector(items, start.to(close)),
+                        Some((start, Frame::Dotted(items, _, Some(tail)))) => items
+                            .into_iter()
+                            .rev()
+                            .fold(tail, |tail, head| T::pair(head, tail, close))
+                            .with_start(start),
+                        _ => unreachable!("the top frame is a closable list"),
+                    };
+                    (obj, close)
+                }
+                None => {
+                    if matches!(stack.last(), Some((_, Frame::List(items, _))) if !items.is_empty())
+                        && after_delimiter(input)
+                    {
+                        if let Ok((_, rest)) = dot.parse(input) {
+                            if let Some((start, Frame::List(items, close))) = stack.pop() {
+                                stack.push((start, Frame::Dotted(items, close, None)));
+                            }
+                            input = rest;
+                            continue;
+                        }
+                    }
+
+                    let start = Span::new(input, input);
+                    let frame = if let Ok((open, rest)) = open.parse(input) {
+                        let close = if open == '[' { ']' } else { ')' };
+                        Some((Frame::List(vec![], close), rest))
+                    } else if let Ok((_, rest)) = open_vector.parse(input) {
+                        Some((Frame::Vector(vec![]), rest))
+                    } else if let Ok(((name, span), rest)) = prefix.parse(input) {
+                        let name = T::atom(LispObject::Ident(name.into()), span);
+                        Some((Frame::Prefix(name), rest))
+                    } else {
+                        None
+                    };
+
+                    if let Some((frame, rest)) = frame {
+                        if stack.len() == config.max_depth {
+                            return Err(Error::new(input));
+                        }
+                        input = rest;
+                        stack.push((start, frame));
+                        continue;
+                    }
+
+                    let ((obj, span), rest) = atom.parse(input).map_err(|e| match unclosed {
+                        Some(unclosed) => unclosed.merge(e),
+                        None => e,
+                    })?;
+                    input = rest;
+                    (T::atom(obj, span), span)
+                }
+            };
+
+            while matches!(stack.last(), Some((_, Frame::Prefix(_)))) {
+                let Some((start, Frame::Prefix(name))) = stack.pop() else {
+                    unreachable!("the top frame is a prefix")
+                };
+                obj = T::list(vec![name, obj], start.to(end));
+            }
+
+            match stack.last_mut() {
+                Some((_, Frame::List(items, _) | Frame::Vector(items))) => {
+                    items.push(obj);
+                    (_, input) = skip.parse(input)?;
+                }
+                Some((_, Frame::Dotted(_, _, tail @ None))) => {
+                    *tail = Some(obj);
+                    (_, input) = skip.parse(input)?;
+                }
+                _ => return Ok((obj, input)),
+            }
+        }
+    })
+}
+
+/// Partially parsed object on the [`tree`] stack.
+enum Frame<T> {
+    /// List with the character that closes it.
+    List(Vec<T>, char),
+    Vector(Vec<T>),
+    /// List after its ` . `, with the tail once it has been parsed.
+    Dotted(Vec<T>, char, Option<T>),
+    /// Reader macro like `'x` or `,@x`, which wraps the next object as
+    /// `(quote x)` or `(unquote-splicing x)`.
+    Prefix(T),
+}
+
+/// Parses `open`, any number of `item`s and then `close`, skipping
+/// whitespace and comments after `open` and after each item.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn delimited_list<O, I, C>(open: O, item: I, close: C) -> impl Parser<Output = Vec<I::Output>>
+where
+    O: Parser,
+    I: Parser,
+    C: Parser,
+{
+    delimited(token(open), many(token(item)), close)
+}
+
+/// Parses a proper list like `(a b c)`, and the whitespace after it. Use
+/// [`lisp_object`] to also accept dotted pairs like `(a . b)`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_list() -> impl Parser<Output = LispObject> {
+    let list = delimited_list(character('('), lisp_object(), character(')'));
+    character('(')
+        .peek()
+        .zip_right(token(list).cut())
+        .map(LispObject::List)
+}
+
+/// Like [`lisp_list`], but for a list in square brackets, as in Clojure or
+/// R6RS Scheme. Inside it, both `(...)` and `[...]` are lists, and each must
+/// be closed by its own kind of bracket.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_bracket_list() -> impl Parser<Output = LispObject> {
+    let config = LispConfig {
+        brackets: true,
+        ..LispConfig::default()
+    };
+    character('[')
+        .peek()
+        .zip_right(config.object_parser().cut())
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn lisp_vector() -> impl Parser<Output = LispObject> {
+    tag("#(").peek().zip_right(lisp_object().cut())
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn quote() -> impl Parser<Output = LispObject> {
+    character('\'').peek().zip_right(lisp_object().cut())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser_comb::{parse, preceded, Expected};
+
+    use super::*;
+
+    #[test]
+    fn test_name() {
+        let (parsed, rest) = string().parse(r#""hello""#).unwrap();
+        assert_eq!(parsed, "hello");
+        assert_eq!(rest, "");
+        assert!(string().parse("").is_err());
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        assert_eq!(
+            Ok((r#"say "hi""#.into(), "")),
+            string().parse(r#""say \"hi\"""#)
+        );
+        assert_eq!(
+            Ok(("line\nbreak".into(), "")),
+            string().parse(r#""line\nbreak""#)
+        );
+        assert_eq!(Ok(("\t\r".into(), "")), string().parse(r#""\t\r""#));
+        assert_eq!(Ok((r"a\b".into(), "")), string().parse(r#""a\\b""#));
+        assert!(string().parse(r#""abc\"#).is_err());
+        assert!(string().parse(r#""\q""#).is_err());
+    }
+
+    #[test]
+    fn test_string_unicode_escapes() {
+        assert_eq!(Ok(("é".into(), "")), string().parse(r#""\u{e9}""#));
+        assert_eq!(Ok(("é 你好".into(), "")), string().parse("\"é 你好\""));
+        assert_eq!(
+            Ok(("a\u{1F600}b".into(), "")),
+            string().parse(r#""a\u{1F600}b""#)
+        );
+        assert!(string().parse(r#""\u{110000}""#).is_err());
+        assert!(string().parse(r#""\u{D800}""#).is_err());
+        assert!(string().parse(r#""\u{1F600""#).is_err());
+        assert!(string().parse(r#""\u{}""#).is_err());
+    }
+
+    #[test]
+    fn test_string_ref() {
+        let input = r#""hello" rest"#;
+        let (parsed, rest) = string_ref(input).unwrap();
+        assert_eq!(parsed, "hello");
+        assert_eq!(rest, " rest");
+        assert!(matches!(parsed, Cow::Borrowed(s) if core::ptr::eq(s, &input[1..6])));
+
+        let (parsed, rest) = string_ref(r#""a\nb""#).unwrap();
+        assert_eq!(parsed, "a\nb");
+        assert_eq!(rest, "");
+        assert!(matches!(parsed, Cow::Owned(_)));
+
+        assert!(string_ref("hello").is_err());
+        assert!(string_ref(r#""abc"#).is_err());
+    }
+
+    #[test]
+    fn test_string_error_position() {
+        let err = parse(string(), r#""ab\q""#).unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+
+        let err = parse(lisp_object(), "(foo\n  \"bar)").unwrap_err();
+        assert_eq!(err.offset(), Some(12));
+    }
+
+    #[test]
+    fn test_ident() {
+        let (parsed, rest) = ident().parse("hello").unwrap();
+        assert_eq!(parsed, "hello");
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn test_ident_symbols() {
+        assert_eq!(
+            Ok(("list->vector".into(), "")),
+            ident().parse("list->vector")
+        );
+        assert_eq!(Ok(("map!".into(), " x")), ident().parse("map! x"));
+        assert_eq!(Ok(("+".into(), ")")), ident().parse("+)"));
+        assert_eq!(Ok(("-".into(), "")), ident().parse("-"));
+        assert_eq!(Ok(("<=".into(), "")), ident().parse("<="));
+        assert_eq!(Ok(("*global*".into(), "")), ident().parse("*global*"));
+        assert_eq!(Ok(("-x1".into(), "")), ident().parse("-x1"));
+        assert!(ident().parse("-5").is_err());
+        assert!(ident().parse("+1").is_err());
+
+        assert_eq!(Ok(LispObject::Number(-5)), parse(lisp_object(), "-5"));
+        assert_eq!(Ok(LispObject::Ratio(1, 2)), parse(lisp_object(), "2/4"));
+        assert_eq!(
+            Ok(LispObject::List(vec![
+                LispObject::Ident("+".into()),
+                LispObject::Number(1),
+                LispObject::Number(-2),
+            ])),
+            parse(lisp_object(), "(+ 1 -2)")
+        );
+    }
+
+    #[test]
+    fn test_ident_ref() {
+        let input = "foo_1 bar";
+        let (span, rest) = ident_ref().parse(input).unwrap();
+        assert_eq!(span.slice(input), "foo_1");
+        assert_eq!(rest, " bar");
+        assert!(core::ptr::eq(span.slice(input), &input[..5]));
+        assert!(ident_ref().parse("1foo").is_err());
+
+        let input = "(foo 1 bar)";
+        let spans = parse(
+            delimited_list(
+                character('('),
+                ident_ref().or_same(number_ref()),
+                character(')'),
+            ),
+            input,
+        )
+        .unwrap();
+        let slices: Vec<_> = spans.into_iter().map(|span| span.slice(input)).collect();
+        assert_eq!(slices, ["foo", "1", "bar"]);
+    }
+
+    #[test]
+    fn test_number_64() {
+        assert_eq!(Ok((3_000_000_000, "")), number_i64().parse("3000000000"));
+        assert_eq!(
+            Ok((i64::MIN, "")),
+            number_i64().parse("-9223372036854775808")
+        );
+        assert_eq!(
+            Ok((u64::MAX, "")),
+            number_u64().parse("18446744073709551615")
+        );
+        assert_eq!(Ok((7, "")), number_u64().parse("+7"));
+        assert!(number().parse("3000000000").is_err());
+        assert!(number_u64().parse("-1").is_err());
+        assert!(number_u64().parse("18446744073709551616").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "bignum")]
+    fn test_bignum() {
+        use num_bigint::BigInt;
+
+        let digits = "12345678901234567890123456789012345678901234567890";
+        let expected: BigInt = digits.parse().unwrap();
+        assert_eq!(Ok((expected.clone(), "")), bignum().parse(digits));
+        assert_eq!(Ok((-BigInt::from(12), "")), bignum().parse("-1_2"));
+        assert!(bignum().parse("x").is_err());
+
+        assert_eq!(
+            Ok(LispObject::List(vec![
+                LispObject::BigInt(expected),
+                LispObject::Number(1),
+            ])),
+            parse(lisp_object(), &format!("({digits} 1)"))
+        );
+        assert_eq!(
+            Ok(LispObject::Float(1e30)),
+            parse(lisp_object(), "1000000000000000000000000000000.0")
+        );
+    }
+
+    #[test]
+    fn test_lisp_ident_ci() {
+        assert_eq!(parse(lisp_ident_ci(), "Foo"), parse(lisp_ident_ci(), "foo"));
+        assert_eq!(
+            Ok(LispObject::Ident("foo".into())),
+            parse(lisp_ident_ci(), "FOO")
+        );
+        assert_eq!(
+            Ok(LispObject::Ident("Foo".into())),
+            parse(lisp_ident(), "Foo")
+        );
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(Ok(((3, 4), "")), ratio().parse("3/4"));
+        assert_eq!(Ok(((-3, 4), " x")), ratio().parse("-6/8 x"));
+        assert_eq!(Ok(((0, 1), "")), ratio().parse("0/5"));
+        assert_eq!(Ok(((5, 1), "")), ratio().parse("10/2"));
+        assert!(ratio().parse("1/0").is_err());
+        assert!(ratio().parse("1/-2").is_err());
+        assert!(ratio().parse("1").is_err());
+
+        assert_eq!(
+            Ok(LispObject::List(vec![
+                LispObject::Ratio(1, 2),
+                LispObject::Number(2),
+            ])),
+            parse(lisp_object(), "(2/4 2)")
+        );
+        assert!(parse(lisp_object(), "1/0").is_err());
+    }
+
+    #[test]
+    fn test_digit_separators() {
+        assert_eq!(Ok((1000, "")), number().parse("1_000"));
+        assert_eq!(Ok((-1_000_000, "")), number().parse("-1_000_000"));
+        assert!(number().parse("_1").is_err());
+        assert!(number().parse("1_").is_err());
+        assert!(number().parse("1__0").is_err());
+
+        assert_eq!(Ok((1_000.000_5, "")), float().parse("1_000.000_5"));
+        assert_eq!(Ok((1e10, "")), float().parse("1e1_0"));
+        assert!(parse(float(), "1._5").is_err());
+        assert!(parse(float(), "1.5_").is_err());
+
+        assert_eq!(
+            Ok(LispObject::Ident("_1".into())),
+            parse(lisp_object(), "_1")
+        );
+    }
+
+    #[test]
+    fn test_hex_number() {
+        assert_eq!(Ok((255, "")), hex_number().parse("0xFF"));
+        assert_eq!(Ok((0xab, " x")), hex_number().parse("0Xab x"));
+        assert_eq!(Ok((0x1f, "g")), hex_number().parse("1fg"));
+        assert_eq!(Ok((i32::MAX, "")), hex_number().parse("0x7fffffff"));
+        assert!(hex_number().parse("0x").is_err());
+        assert!(hex_number().parse("0xZ").is_err());
+        assert!(hex_number().parse("0x80000000").is_err());
+    }
+
+    #[test]
+    fn test_radix_number() {
+        assert_eq!(Ok((31, "")), radix_number().parse("#x1F"));
+        assert_eq!(Ok((15, " x")), radix_number().parse("#o17 x"));
+        assert_eq!(Ok((10, ")")), radix_number().parse("#b1010)"));
+        assert_eq!(Ok((-255, "")), radix_number().parse("#X-ff"));
+        assert!(radix_number().parse("#b2").is_err());
+        assert!(radix_number().parse("#o8").is_err());
+        assert!(radix_number().parse("#b").is_err());
+        assert!(radix_number().parse("#t").is_err());
+
+        assert_eq!(
+            Ok(LispObject::List(vec![
+                LispObject::Number(255),
+                LispObject::Number(5),
+                LispObject::Bool(true),
+            ])),
+            parse(lisp_object(), "(#xff #b101 #t)")
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        let (parsed, rest) = number().parse("123").unwrap();
+        assert_eq!(parsed, 123);
+        assert_eq!(rest, "");
+        assert!(number().parse("").is_err());
+        assert!(number().parse("abc").is_err());
+
+        let (parsed, rest) = number().parse("12x").unwrap();
+        assert_eq!(parsed, 12);
+        assert_eq!(rest, "x");
+
+        let (parsed, rest) = number().parse("0123456789").unwrap();
+        assert_eq!(parsed, 123_456_789);
+        assert_eq!(rest, "");
+    }
+
+    #[test]
+    fn test_signed_number() {
+        assert_eq!(Ok((-42, "")), number().parse("-42"));
+        assert_eq!(Ok((7, "")), number().parse("+7"));
+        assert_eq!(Ok((0, "")), number().parse("-0"));
+        assert_eq!(Ok((3, "-4")), number().parse("3-4"));
+        assert_eq!(Ok((i32::MIN, "")), number().parse("-2147483648"));
+        assert!(number().parse("-").is_err());
+        assert!(number().parse("+-1").is_err());
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_float() {
+        assert_eq!(Ok((3.14, "")), float().parse("3.14"));
+        assert_eq!(Ok((1e10, "")), float().parse("1e10"));
+        assert_eq!(Ok((-2.5e-3, "")), float().parse("-2.5e-3"));
+        assert_eq!(Ok((-0.5, "")), float().parse("-0.5"));
+        assert!(float().parse("1e").is_err());
+        assert!(float().parse("1e+x").is_err());
+        assert_eq!(Ok((3.0, ".")), float().parse("3."));
+        assert!(float().parse(".").is_err());
+        assert!(float().parse("").is_err());
+
+        assert_eq!(Ok((f64::INFINITY, " x")), float().parse("+inf.0 x"));
+        assert_eq!(Ok((f64::NEG_INFINITY, "")), float().parse("-inf.0"));
+        assert!(float().parse("+nan.0").is_ok_and(|(n, _)| n.is_nan()));
+        assert!(float().parse("inf").is_err());
+        assert_eq!(
+            parse(lisp_object(), "(+inf.0 +inf)"),
+            Ok(LispObject::List(vec![
+                LispObject::Float(f64::INFINITY),
+                LispObject::Ident("+inf".into())
+            ]))
+        );
+        assert!(parse(lisp_object(), "(-inf.0x)").is_err());
+    }
+
+    #[test]
+    fn test_number_ref() {
+        fn number_slice(input: &str) -> Option<(&str, &str)> {
+            let (span, rest) = number_ref().parse(input).ok()?;
+            Some((span.slice(input), rest))
+        }
+
+        assert_eq!(Some(("007", "")), number_slice("007"));
+        assert_eq!(Some(("-1_000.50e3", " x")), number_slice("-1_000.50e3 x"));
+        assert_eq!(Some(("+4", ")")), number_slice("+4)"));
+        assert_eq!(Some(("1", "/2")), number_slice("1/2"));
+        assert_eq!(None, number_slice("x1"));
+        assert_eq!(None, number_slice("1__0"));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
+    fn test_lisp_float() {
+        use LispObject::*;
+
+        let parsed = parse(lisp_object(), "(pi 3.14159)").unwrap();
+        assert_eq!(parsed, List(vec![Ident("pi".into()), Float(3.14159)]));
+    }
+
+    #[test]
+    fn test_lisp_number() {
+        use LispObject::*;
+
+        assert_eq!(Ok((Number(-5), "")), lisp_number().parse("-5"));
+        assert!(lisp_number().parse("1.5").is_err());
+        assert!(lisp_number().parse("1e3").is_err());
+        assert!(lisp_number().parse("1x").is_err());
+        assert_eq!(Ok((Number(1), ")")), lisp_number().parse("1)"));
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn ident() -> impl Parser<Output = String> {
-    from_fn(move |input| {
-        let mut first = character('_')
-            .or(range('a'..='z'))
-            .or(range('A'..='Z'))
-            .map(Get::get);
-        let mut other = many(
-            character('_')
-                .or(range('a'..='z'))
-                .or(range('A'..='Z'))
-                .or(range('0'..='9'))
-                .map(Get::get),
+        let parsed = parse(lisp_object(), "(add 1 2 -3)").unwrap();
+        assert_eq!(
+            parsed,
+            List(vec![Ident("add".into()), Number(1), Number(2), Number(-3)])
         );
 
-        let (first_char, rest): (char, _) = first.parse(input)?;
-        let (parsed, rest): (Vec<char>, _) = other.parse(rest)?;
+        let parsed = parse(lisp_object(), "(1 1.5)").unwrap();
+        assert_eq!(parsed, List(vec![Number(1), Float(1.5)]));
 
-        Ok((
-            [vec![first_char], parsed].concat().into_iter().collect(),
-            rest,
-        ))
-    })
-}
+        for input in ["(1x)", "(1+ x)", "(-2_a)", "(3000000000x)"] {
+            assert!(parse(lisp_object(), input).is_err(), "{input}");
+        }
+        let err = parse(lisp_object(), "(f 1x)").unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+        assert_eq!(err.label(), Some("a delimiter after the number"));
+    }
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn number() -> impl Parser<Output = i32> {
-    from_fn(move |input| {
-        let mut parser = many(range('0'..='9'));
+    #[test]
+    fn test_lisp_object_is_send() {
+        fn assert_send<T: Send>(_: &T) {}
 
-        let (parsed, rest) = parser.parse(input)?;
-        if let Ok(n) = parsed.into_iter().collect::<String>().parse() {
-            Ok((n, rest))
-        } else {
-            Err(Error)
-        }
-    })
-}
+        assert_send(&lisp_object());
+        assert_send(&lisp_object_spanned());
+        assert_send(&lisp_object_with_max_depth(8));
+    }
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn lisp_string() -> impl Parser<Output = LispObject> {
-    string().map(LispObject::String)
-}
+    #[test]
+    fn test_lisp_atom_numbers() {
+        use LispObject::*;
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn lisp_ident() -> impl Parser<Output = LispObject> {
-    ident().map(LispObject::Ident)
-}
+        let mut atom = lisp_atom();
+        let inputs = ["-1/2", "-123", "-1e2", "abcd", "1.5)"].map(str::to_owned);
+        let parsed: Vec<_> = inputs.iter().map(|input| atom.parse(input)).collect();
+        assert_eq!(
+            parsed,
+            [
+                Ok((Ratio(-1, 2), "")),
+                Ok((Number(-123), "")),
+                Ok((Float(-100.0), "")),
+                Ok((Ident("abcd".into()), "")),
+                Ok((Float(1.5), ")")),
+            ]
+        );
+    }
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn lisp_object() -> impl Parser<Output = LispObject> {
-    from_fn(move |input| {
-        lisp_string()
-            .or(lisp_ident())
-            .or(lisp_list())
-            .map(Get::get)
-            .parse(input)
-    })
-}
+    #[test]
+    fn test_lisp_number_end() {
+        use LispObject::*;
 
-#[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn lisp_list() -> impl Parser<Output = LispObject> {
-    character('(')
-        .zip_left(many(whitespace()))
-        .zip_right(many(lisp_object().zip_left(many(whitespace()))))
-        .zip_left(many(whitespace()))
-        .zip_left(character(')'))
-        .zip_left(many(whitespace()))
-        .map(LispObject::List)
-}
+        for input in ["(1e)", "(1e+ x)", "(1.5x)", "(1/2x)", "(1/0)"] {
+            assert!(parse(lisp_object(), input).is_err(), "{input}");
+        }
+        let err = parse(lisp_object(), "(a 1/2x)").unwrap_err();
+        assert_eq!(err.offset(), Some(6));
+        assert_eq!(err.label(), Some("a delimiter after the number"));
 
-#[cfg(test)]
-mod tests {
-    use crate::parser_comb::Error;
+        assert_eq!(
+            parse(lisp_object(), "(1/2(1.5)\"s\"'2e1)"),
+            Ok(List(vec![
+                Ratio(1, 2),
+                List(vec![Float(1.5)]),
+                String("s".into()),
+                List(vec![Ident("quote".into()), Float(20.0)]),
+            ]))
+        );
+        assert!(lisp_float().parse("1.5x").is_err());
+        assert!(lisp_ratio().parse("1/2x").is_err());
+    }
 
-    use super::*;
+    #[test]
+    fn test_lisp_number_overflow() {
+        use LispObject::*;
+
+        let parsed = parse(lisp_object(), "3000000000.0").unwrap();
+        assert_eq!(parsed, Float(3e9));
+
+        let result = parse(lisp_object(), "(1 3000000000)");
+        #[cfg(not(feature = "bignum"))]
+        {
+            let err = result.unwrap_err();
+            assert_eq!(err.offset(), Some(3));
+            assert_eq!(err.label(), Some("an integer that fits in an i32"));
+        }
+        #[cfg(feature = "bignum")]
+        assert_eq!(
+            result,
+            Ok(List(vec![Number(1), BigInt(3_000_000_000_i64.into())]))
+        );
+    }
 
     #[test]
-    fn test_name() {
-        let (parsed, rest) = string().parse(r#""hello""#).unwrap();
-        assert_eq!(parsed, "hello");
-        assert_eq!(rest, "");
-        assert_eq!(Err(Error), string().parse(""));
+    fn test_lisp_bool() {
+        use LispObject::*;
+
+        assert_eq!(Ok(Bool(true)), parse(lisp_bool(), "#t"));
+        assert_eq!(Ok(Bool(false)), parse(lisp_bool(), "#f"));
+        assert_eq!(Ok(Bool(true)), parse(lisp_bool(), "#true"));
+        assert_eq!(Ok(Bool(false)), parse(lisp_bool(), "#false"));
+        assert!(lisp_bool().parse("#tx").is_err());
+        assert!(lisp_bool().parse("#t1").is_err());
+        assert!(lisp_bool().parse("#").is_err());
+
+        assert_eq!(
+            Ok(List(vec![Bool(true), Bool(false)])),
+            parse(lisp_object(), "(#t #false)")
+        );
+        assert_eq!(Ok(List(vec![Bool(true)])), parse(lisp_object(), "(#t)"));
+        assert!(parse(lisp_object(), "(#tx)").is_err());
     }
 
     #[test]
-    fn test_ident() {
-        let (parsed, rest) = ident().parse("hello").unwrap();
-        assert_eq!(parsed, "hello");
-        assert_eq!(rest, "");
+    fn test_lisp_keyword() {
+        use LispObject::*;
+
+        assert_eq!(Ok(Keyword("foo".into())), parse(lisp_keyword(), ":foo"));
+        assert_eq!(
+            Ok(List(vec![
+                Keyword("a".into()),
+                Number(1),
+                Keyword("b".into()),
+                Number(2)
+            ])),
+            parse(lisp_object(), "(:a 1 :b 2)")
+        );
+        assert!(parse(lisp_keyword(), ":").is_err());
+        assert!(parse(lisp_object(), "(:)").is_err());
     }
 
     #[test]
-    fn test_number() {
-        let (parsed, rest) = number().parse("123").unwrap();
-        assert_eq!(parsed, 123);
-        assert_eq!(rest, "");
-        assert_eq!(Err(Error), number().parse(""));
-        assert_eq!(Err(Error), number().parse("asd"));
+    fn test_lisp_char() {
+        use LispObject::*;
+
+        assert_eq!(Ok(Char('a')), parse(lisp_char(), "#\\a"));
+        assert_eq!(Ok(Char('(')), parse(lisp_char(), "#\\("));
+        assert_eq!(Ok(Char(' ')), parse(lisp_char(), "#\\ "));
+        assert_eq!(Ok(Char(' ')), parse(lisp_char(), "#\\space"));
+        assert_eq!(Ok(Char('\n')), parse(lisp_char(), "#\\newline"));
+        assert_eq!(Ok(Char('\t')), parse(lisp_char(), "#\\Tab"));
+        assert!(parse(lisp_char(), "#\\").is_err());
+        assert!(parse(lisp_char(), "#\\spaces").is_err());
+        assert!(parse(lisp_char(), "#a").is_err());
+
+        assert_eq!(
+            Ok(List(vec![Char('a'), Char(')'), Char(' ')])),
+            parse(lisp_object(), "(#\\a #\\) #\\space)")
+        );
     }
 
     #[test]
@@ -117,7 +1511,35 @@ mod tests {
         let (parsed, rest) = lisp_string().parse(r#""ayo""#).unwrap();
         assert_eq!(parsed, LispObject::String("ayo".into()));
         assert_eq!(rest, "");
-        assert_eq!(Err(Error), lisp_string().parse(""));
+        assert!(lisp_string().parse("").is_err());
+    }
+
+    #[test]
+    fn test_raw_string() {
+        assert_eq!(
+            Ok((r#"say "hi" \n C:\dir"#.into(), " x")),
+            raw_string().parse(r##"#"say "hi" \n C:\dir"# x"##)
+        );
+        assert_eq!(
+            Ok((r##"a "#quoted"# word"##.into(), "")),
+            raw_string().parse(r###"##"a "#quoted"# word"##"###)
+        );
+        assert_eq!(
+            Ok(("line\n\\".into(), "")),
+            raw_string().parse("#\"line\n\\\"#")
+        );
+        assert_eq!(Ok((String::new(), "")), raw_string().parse(r##"#""#"##));
+        assert!(raw_string().parse(r#""no hashes""#).is_err());
+        assert!(raw_string().parse(r##"#"unterminated""##).is_err());
+        assert!(raw_string().parse(r###"##"short"#"###).is_err());
+
+        assert_eq!(
+            Ok(LispObject::List(vec![
+                LispObject::Ident("print".into()),
+                LispObject::String(r"\d+".into()),
+            ])),
+            parse(lisp_object(), r##"(print #"\d+"#)"##)
+        );
     }
 
     #[test]
@@ -125,7 +1547,7 @@ mod tests {
         let (parsed, rest) = lisp_ident().parse("foo").unwrap();
         assert_eq!(parsed, LispObject::Ident("foo".into()));
         assert_eq!(rest, "");
-        assert_eq!(Err(Error), lisp_ident().parse(""));
+        assert!(lisp_ident().parse("").is_err());
     }
 
     #[test]
@@ -134,7 +1556,158 @@ mod tests {
         assert_eq!(parsed, LispObject::List(vec![]));
         assert_eq!(rest, "");
 
-        assert_eq!(Err(Error), lisp_list().parse(""));
+        assert!(lisp_list().parse("").is_err());
+
+        let err = parse(lisp_list().or_same(lisp_ident()), "(a b").unwrap_err();
+        assert!(err.is_cut());
+        assert_eq!(err.offset(), Some(4));
+        assert!(!lisp_list().parse("a").unwrap_err().is_cut());
+    }
+
+    #[test]
+    fn test_delimited_list() {
+        let mut parens = delimited_list(character('('), number(), character(')'));
+        let mut brackets = delimited_list(character('['), number(), character(']'));
+        assert_eq!(Ok((vec![1, 2, 3], "")), parens.parse("( 1 2\n3 )"));
+        assert_eq!(
+            Ok((vec![1, 2, 3], " x")),
+            brackets.parse("[1 ; one\n2 3] x")
+        );
+        assert_eq!(Ok((vec![], "")), brackets.parse("[]"));
+        assert!(brackets.parse("[1 2)").is_err());
+        assert!(parens.parse("(1 a)").is_err());
+
+        assert_eq!(
+            Ok(LispObject::List(vec![
+                LispObject::Ident("a".into()),
+                LispObject::List(vec![]),
+            ])),
+            parse(lisp_list(), "( a () )")
+        );
+        assert!(parse(lisp_list(), "(a . b)").is_err());
+    }
+
+    #[test]
+    fn test_lisp_bracket_list() {
+        use LispObject::*;
+
+        assert_eq!(
+            Ok(List(vec![Number(1), Number(2), Number(3)])),
+            parse(lisp_bracket_list(), "[1 2 3]")
+        );
+        assert_eq!(
+            Ok(List(vec![
+                Ident("let".into()),
+                List(vec![List(vec![Ident("x".into()), Number(1)])]),
+            ])),
+            parse(lisp_bracket_list(), "[let ([x 1])]")
+        );
+        assert!(lisp_bracket_list().parse("(a)").is_err());
+
+        let err = parse(lisp_bracket_list(), "[a (b])").unwrap_err();
+        assert_eq!(err.offset(), Some(5));
+        assert_eq!(err.expected(), Some(&Expected::Char(')')));
+        let err = parse(lisp_bracket_list(), "[a)").unwrap_err();
+        assert_eq!(err.expected(), Some(&Expected::Char(']')));
+        let err = parse(lisp_object(), "(a]").unwrap_err();
+        assert_eq!(err.expected(), Some(&Expected::Char(')')));
+        assert!(parse(lisp_object(), "#(a]").is_err());
+    }
+
+    #[test]
+    fn test_parse_forms() {
+        use LispObject::*;
+
+        assert_eq!(
+            Ok(vec![
+                List(vec![Ident("a".into())]),
+                List(vec![Ident("b".into())]),
+                List(vec![Ident("d".into())]),
+            ]),
+            parse_forms("(a) (b) ; c\n(d)")
+        );
+        assert_eq!(
+            Ok(vec![Ident("x".into()), Number(1)]),
+            parse_forms(" ; header\n x 1 ")
+        );
+        assert_eq!(Ok(vec![]), parse_forms(" ; nothing"));
+        assert_eq!(parse_forms("(a) (b").unwrap_err().offset(), Some(6));
+        assert!(parse_forms("(a) )").is_err());
+    }
+
+    #[test]
+    fn test_lisp_list_crlf() {
+        use LispObject::*;
+
+        let expected = List(vec![
+            Ident("a".into()),
+            List(vec![Ident("b".into())]),
+            Ident("c".into()),
+        ]);
+        assert_eq!(
+            Ok(expected.clone()),
+            parse(lisp_list(), "(a\r\n(b ; note\r\n)\r\n c)\r\n")
+        );
+        assert_eq!(Ok(expected), parse(lisp_list(), "(\ra\r(b)\rc\r)"));
+    }
+
+    #[test]
+    fn test_token() {
+        assert_eq!(Ok(("foo".into(), "")), token(ident()).parse("foo   "));
+        assert_eq!(
+            Ok(("foo".into(), "bar")),
+            token(ident()).parse("foo ; c\n #| b |# bar")
+        );
+        assert!(token(ident()).parse(" foo").is_err());
+    }
+
+    #[test]
+    fn test_skip_whitespace() {
+        assert_eq!(
+            Ok(((), "x")),
+            skip_whitespace().parse(" \r\n\t; c\r\n#| b |#x")
+        );
+        assert_eq!(Ok(((), "x")), skip_whitespace().parse("x"));
+    }
+
+    #[test]
+    fn test_comment() {
+        assert_eq!(Ok(((), "\nrest")), comment().parse("; note\nrest"));
+        assert_eq!(Ok(((), "")), comment().parse("; no newline"));
+        assert_eq!(Ok(((), "")), comment().parse(";"));
+        assert!(comment().parse("x").is_err());
+        assert_eq!(Ok(((), "\nrest")), line_comment('#').parse("# note\nrest"));
+        assert!(line_comment('#').parse("; note").is_err());
+    }
+
+    #[test]
+    fn test_block_comment() {
+        assert_eq!(Ok(((), " x")), block_comment().parse("#| a |# x"));
+        assert_eq!(
+            Ok(((), "")),
+            block_comment().parse("#| outer #| inner |# still outer |#")
+        );
+        assert!(block_comment().parse("#| outer #| inner |#").is_err());
+        assert!(block_comment().parse("#|").is_err());
+        assert!(block_comment().parse("x").is_err());
+    }
+
+    #[test]
+    fn test_lisp_comments() {
+        use LispObject::*;
+
+        let parsed = parse(lisp_object(), "(a ; note\n b)").unwrap();
+        assert_eq!(parsed, List(vec![Ident("a".into()), Ident("b".into())]));
+
+        let parsed = parse(lisp_object(), "(; leading\n a) ; trailing").unwrap();
+        assert_eq!(parsed, List(vec![Ident("a".into())]));
+
+        assert!(parse(lisp_object(), "(a ; b)").is_err());
+
+        let parsed = parse(lisp_object(), "(a #| x #| y |# |# b)").unwrap();
+        assert_eq!(parsed, List(vec![Ident("a".into()), Ident("b".into())]));
+
+        assert!(parse(lisp_object(), "(a #| b)").is_err());
     }
 
     #[test]
@@ -161,4 +1734,253 @@ mod tests {
         );
         assert_eq!(rest, "");
     }
+
+    #[test]
+    fn test_lisp_deeply_nested() {
+        let depth = 1000;
+        let input = format!("{}{}", "(".repeat(depth), ")".repeat(depth));
+
+        let mut parsed = parse(lisp_object_with_max_depth(depth), &input).unwrap();
+        for _ in 1..depth {
+            let LispObject::List(mut list) = parsed else {
+                panic!("expected a list");
+            };
+            assert_eq!(list.len(), 1);
+            parsed = list.pop().unwrap();
+        }
+        assert_eq!(parsed, LispObject::List(vec![]));
+    }
+
+    #[test]
+    fn test_lisp_max_depth() {
+        let nested = |depth| format!("{}{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(parse(lisp_object(), &nested(MAX_DEPTH)).is_ok());
+
+        let err = parse(lisp_object(), &nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(err.offset(), Some(MAX_DEPTH));
+
+        assert!(parse(lisp_object_with_max_depth(2), "((a) (b))").is_ok());
+        assert!(parse(lisp_object_with_max_depth(2), "((a) ((b)))").is_err());
+        assert!(parse(lisp_object_with_max_depth(0), "a").is_ok());
+        assert!(parse(lisp_object_with_max_depth(0), "()").is_err());
+
+        assert!(parse(lisp_object(), &"(".repeat(100_000)).is_err());
+        assert!(parse(lisp_object(), &nested(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        use LispObject::*;
+
+        let quoted = |obj| List(vec![Ident("quote".into()), obj]);
+
+        assert_eq!(Ok(quoted(Ident("foo".into()))), parse(quote(), "'foo"));
+        assert_eq!(
+            Ok(quoted(List(vec![Ident("a".into()), Ident("b".into())]))),
+            parse(quote(), "'(a b)")
+        );
+        assert_eq!(Ok(quoted(quoted(Ident("x".into())))), parse(quote(), "''x"));
+        assert_eq!(
+            Ok(List(vec![
+                Ident("f".into()),
+                quoted(Number(1)),
+                Ident("g".into())
+            ])),
+            parse(lisp_object(), "(f '1 g)")
+        );
+        assert!(parse(quote(), "foo").is_err());
+        assert!(parse(lisp_object(), "'").is_err());
+        assert!(parse(lisp_object(), "(a ')").is_err());
+    }
+
+    #[test]
+    fn test_quasiquote() {
+        use LispObject::*;
+
+        let wrap = |name: &str, obj| List(vec![Ident(name.into()), obj]);
+
+        assert_eq!(
+            Ok(wrap(
+                "quasiquote",
+                List(vec![
+                    Ident("a".into()),
+                    wrap("unquote", Ident("b".into())),
+                    wrap("unquote-splicing", Ident("c".into())),
+                ])
+            )),
+            parse(lisp_object(), "`(a ,b ,@c)")
+        );
+        assert_eq!(
+            Ok(wrap("unquote", wrap("quote", Ident("x".into())))),
+            parse(lisp_object(), ",'x")
+        );
+        assert!(parse(lisp_object(), ",@").is_err());
+    }
+
+    #[test]
+    fn test_lisp_dotted_pair() {
+        use LispObject::*;
+
+        let pair = |a, b| Pair(Box::new(a), Box::new(b));
+
+        assert_eq!(
+            Ok(pair(Ident("a".into()), Ident("b".into()))),
+            parse(lisp_object(), "(a . b)")
+        );
+        assert_eq!(
+            Ok(pair(
+                Ident("a".into()),
+                pair(Ident("b".into()), Ident("c".into()))
+            )),
+            parse(lisp_object(), "(a b . c)")
+        );
+        assert_eq!(
+            Ok(List(vec![pair(Number(1), List(vec![Number(2)]))])),
+            parse(lisp_object(), "((1 . (2)))")
+        );
+
+        assert!(parse(lisp_object(), ".").is_err());
+        assert!(parse(lisp_object(), "(. x)").is_err());
+        assert!(parse(lisp_object(), "(a .)").is_err());
+        assert!(parse(lisp_object(), "(a . b c)").is_err());
+        assert!(parse(lisp_object(), "(a . . b)").is_err());
+
+        assert_eq!(
+            Ok(pair(List(vec![]), List(vec![Ident("b".into())]))),
+            parse(lisp_object(), "(().(b))")
+        );
+        assert_eq!(
+            Ok(pair(String("a".into()), Ident("b".into()))),
+            parse(lisp_object(), "(\"a\". b)")
+        );
+        assert!(parse(lisp_object(), "(a.b)").is_err());
+        assert!(parse(lisp_object(), "(a. b)").is_err());
+        assert!(parse(lisp_object(), "(a .b)").is_err());
+    }
+
+    #[test]
+    fn test_lisp_vector() {
+        use LispObject::*;
+
+        assert_eq!(Ok(Vector(vec![])), parse(lisp_vector(), "#()"));
+        assert_eq!(
+            Ok(Vector(vec![Number(1), Number(2), Number(3)])),
+            parse(lisp_vector(), "#(1 2 3)")
+        );
+        assert_eq!(
+            Ok(Vector(vec![
+                List(vec![Ident("a".into())]),
+                Ident("b".into())
+            ])),
+            parse(lisp_vector(), "#((a) b)")
+        );
+        assert_eq!(
+            Ok(List(vec![Vector(vec![Vector(vec![])])])),
+            parse(lisp_object(), "(#(#()))")
+        );
+        assert!(parse(lisp_vector(), "(1 2)").is_err());
+        assert!(parse(lisp_vector(), "#(1 . 2)").is_err());
+        assert!(parse(lisp_vector(), "#(1 2").is_err());
+    }
+
+    #[test]
+    fn test_lisp_config() {
+        use LispObject::*;
+
+        let scheme = LispConfig {
+            brackets: true,
+            ..LispConfig::default()
+        };
+        let common_lisp = LispConfig {
+            fold_case: true,
+            ..LispConfig::default()
+        };
+        let basic = LispConfig {
+            symbol_chars: false,
+            line_comment: Some('#'),
+            block_comments: false,
+            ..LispConfig::default()
+        };
+
+        let input = "[Let ((X 1)) X]";
+        assert_eq!(
+            Ok(List(vec![
+                Ident("Let".into()),
+                List(vec![List(vec![Ident("X".into()), Number(1)])]),
+                Ident("X".into()),
+            ])),
+            parse(scheme.object_parser(), input)
+        );
+        assert!(parse(common_lisp.object_parser(), input).is_err());
+
+        let input = "(Car :Key) ; note";
+        assert_eq!(
+            Ok(List(vec![Ident("Car".into()), Keyword("Key".into())])),
+            parse(scheme.object_parser(), input)
+        );
+        assert_eq!(
+            Ok(List(vec![Ident("car".into()), Keyword("key".into())])),
+            parse(common_lisp.object_parser(), input)
+        );
+
+        assert_eq!(
+            Ok(List(vec![Ident("a".into()), Ident("b".into())])),
+            parse(basic.object_parser(), "(a # comment\n b)")
+        );
+        assert!(parse(basic.object_parser(), "(a ; b)").is_err());
+        assert!(parse(basic.object_parser(), "list->vector").is_err());
+        assert_eq!(
+            parse(lisp_object(), "(a . (b)) ; c"),
+            parse(LispConfig::default().object_parser(), "(a . (b)) ; c")
+        );
+    }
+
+    #[test]
+    fn test_lisp_object_spanned() {
+        let input = "(a (b c)) ";
+        let obj = parse(lisp_object_spanned(), input).unwrap();
+        assert_eq!(obj.span.locate(input), 0..9);
+
+        let SpannedNode::List(items) = &obj.node else {
+            panic!("expected a list");
+        };
+        assert_eq!(items[0].span.locate(input), 1..2);
+        assert_eq!(items[1].span.locate(input), 3..8);
+
+        let SpannedNode::List(inner) = &items[1].node else {
+            panic!("expected a list");
+        };
+        assert_eq!(inner[1].node, SpannedNode::Ident("c".into()));
+        assert_eq!(inner[1].span.locate(input), 6..7);
+
+        let input = "( 'x  . #(1) )";
+        let obj = parse(lisp_object_spanned(), input).unwrap();
+        let SpannedNode::Pair(head, tail) = &obj.node else {
+            panic!("expected a pair");
+        };
+        assert_eq!(obj.span.locate(input), 0..14);
+        assert_eq!(head.span.locate(input), 2..4);
+        assert_eq!(tail.span.locate(input), 8..12);
+
+        let SpannedNode::List(quoted) = &head.node else {
+            panic!("expected a list");
+        };
+        assert_eq!(quoted[0].node, SpannedNode::Ident("quote".into()));
+        assert_eq!(quoted[0].span.locate(input), 2..3);
+        assert_eq!(quoted[1].span.locate(input), 3..4);
+
+        let input = "(a) (b c)";
+        let forms = parse(many(token(lisp_object_spanned())), input).unwrap();
+        let spans: Vec<_> = forms.iter().map(|form| form.span.locate(input)).collect();
+        assert_eq!(spans, [0..3, 4..9]);
+        let SpannedNode::List(items) = &forms[1].node else {
+            panic!("expected a list");
+        };
+        assert_eq!(items[1].span.slice(input), "c");
+
+        let input = "  x";
+        let obj = parse(preceded(skip_whitespace(), lisp_object_spanned()), input).unwrap();
+        assert_eq!(obj.span.locate(input), 2..3);
+    }
 }
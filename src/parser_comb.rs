@@ -1,7 +1,321 @@
-use std::ops::RangeInclusive;
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    rc::Rc,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    fmt,
+    marker::PhantomData,
+    ops::{Range, RangeInclusive},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+#[cfg(feature = "std")]
+use std::io;
+
+/// Parsing error.
+///
+/// Parsers only see the slice of input they are given, so an error first
+/// records how much input was left when the parser failed. [`parse`] then
+/// resolves that against the whole input into a [`Position`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    remaining: usize,
+    position: Option<Position>,
+    label: Option<&'static str>,
+    expected: Option<Expected>,
+    cut: bool,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(Position {
+                offset,
+                line,
+                column,
+            }) => write!(
+                f,
+                "parse error at byte {offset} (line {line}, column {column})"
+            )?,
+            None => f.write_str("parse error")?,
+        }
+        if let Some(label) = self.label {
+            write!(f, ": expected {label}")?;
+        } else if let Some(expected) = &self.expected {
+            write!(f, ": expected {expected}")?;
+        }
+        Ok(())
+    }
+}
 
+impl core::error::Error for Error {}
+
+/// What a primitive parser was looking for when it failed.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Error;
+pub enum Expected {
+    /// A specific character, from [`character`].
+    Char(char),
+    /// Any of these characters, from [`one_of`].
+    OneOf(Cow<'static, str>),
+    /// A character in this range, from [`range`].
+    Range(RangeInclusive<char>),
+    /// A character matching a predicate, from [`satisfy`].
+    Satisfy,
+    /// Any character but these, from [`none_of`].
+    NoneOf(Cow<'static, str>),
+    /// The end of the input, from [`eof`] and [`parse`].
+    End,
+    /// Any of these, from alternatives that failed at the same point.
+    Alternatives(Vec<Expected>),
+}
+
+impl Expected {
+    /// Expects either `self` or `other`, leaving out duplicates.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Alternatives(mut alternatives), Self::Alternatives(other)) => {
+                for expected in other {
+                    if !alternatives.contains(&expected) {
+                        alternatives.push(expected);
+                    }
+                }
+                Self::Alternatives(alternatives)
+            }
+            (Self::Alternatives(mut alternatives), expected) => {
+                if !alternatives.contains(&expected) {
+                    alternatives.push(expected);
+                }
+                Self::Alternatives(alternatives)
+            }
+            (expected, Self::Alternatives(mut alternatives)) => {
+                if !alternatives.contains(&expected) {
+                    alternatives.insert(0, expected);
+                }
+                Self::Alternatives(alternatives)
+            }
+            (first, second) if first == second => first,
+            (first, second) => {
+                let mut alternatives = Vec::with_capacity(4);
+                alternatives.extend([first, second]);
+                Self::Alternatives(alternatives)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Char(c) => write!(f, "{c:?}"),
+            Self::OneOf(chars) => write!(f, "one of {chars:?}"),
+            Self::Range(range) => write!(f, "{:?}..={:?}", range.start(), range.end()),
+            Self::Satisfy => f.write_str("a matching character"),
+            Self::NoneOf(chars) => write!(f, "none of {chars:?}"),
+            Self::End => f.write_str("end of input"),
+            Self::Alternatives(alternatives) => {
+                for (i, expected) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" or ")?;
+                    }
+                    write!(f, "{expected}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Location of an [`Error`] in the input passed to [`parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// Byte offset from the start of the input.
+    pub offset: usize,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, counted in characters.
+    pub column: usize,
+}
+
+/// Part of the input consumed by a parser, from [`Parser::spanned`].
+///
+/// Like an [`Error`], a span only records how much input was left where it
+/// starts and ends, since parsers only see the rest of the input.
+/// [`Span::locate`] resolves it against the whole input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    /// Span of what a parser consumed from `input`, if it left `rest`.
+    #[must_use]
+    pub fn new(input: &str, rest: &str) -> Self {
+        Self {
+            start: input.len(),
+            end: rest.len(),
+        }
+    }
+
+    /// Byte range of the span in the whole `input`.
+    #[must_use]
+    pub fn locate(self, input: &str) -> Range<usize> {
+        input.len().saturating_sub(self.start)..input.len().saturating_sub(self.end)
+    }
+
+    /// The spanned part of the whole `input`.
+    #[must_use]
+    pub fn slice(self, input: &str) -> &str {
+        &input[self.locate(input)]
+    }
+
+    /// Length of the span in bytes.
+    #[must_use]
+    pub fn len(self) -> usize {
+        self.start - self.end
+    }
+
+    /// Whether the parser consumed nothing.
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.start == self.end
+    }
+
+    /// Span from the start of `self` to the end of `other`.
+    #[must_use]
+    pub fn to(self, other: Self) -> Self {
+        Self {
+            start: self.start,
+            end: other.end,
+        }
+    }
+}
+
+impl Error {
+    /// Creates an error for a parser that failed at the start of `input`.
+    #[must_use]
+    pub fn new(input: &str) -> Self {
+        Self {
+            remaining: input.len(),
+            position: None,
+            label: None,
+            expected: None,
+            cut: false,
+        }
+    }
+
+    /// Resolves the failure point against the whole `input`.
+    ///
+    /// [`parse`] does this automatically.
+    #[must_use]
+    pub fn locate(self, input: &str) -> Self {
+        let offset = input.len().saturating_sub(self.remaining);
+        let consumed = &input[..offset];
+        let line_start = consumed.rfind('\n').map_or(0, |i| i + 1);
+
+        Self {
+            position: Some(Position {
+                offset,
+                line: consumed.matches('\n').count() + 1,
+                column: consumed[line_start..].chars().count() + 1,
+            }),
+            ..self
+        }
+    }
+
+    /// Where the error occurred, if it has been located.
+    #[must_use]
+    pub fn position(&self) -> Option<Position> {
+        self.position
+    }
+
+    /// Byte offset of the error, if it has been located.
+    #[must_use]
+    pub fn offset(&self) -> Option<usize> {
+        self.position.map(|p| p.offset)
+    }
+
+    /// What was expected, as given to [`Parser::context`].
+    #[must_use]
+    pub fn label(&self) -> Option<&'static str> {
+        self.label
+    }
+
+    /// Sets what was expected at the failure point.
+    #[must_use]
+    pub fn with_label(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// What the primitive parser that failed was looking for.
+    ///
+    /// Unlike [`Error::label`], this is filled in by the parsers themselves.
+    #[must_use]
+    pub fn expected(&self) -> Option<&Expected> {
+        self.expected.as_ref()
+    }
+
+    /// Sets what the failing primitive parser was looking for.
+    #[must_use]
+    pub fn with_expected(self, expected: Expected) -> Self {
+        Self {
+            expected: Some(expected),
+            ..self
+        }
+    }
+
+    /// Whether the error happened past a [`Parser::cut`], so alternatives
+    /// must not be tried.
+    #[must_use]
+    pub fn is_cut(&self) -> bool {
+        self.cut
+    }
+
+    /// Combines the errors of two alternatives that both failed.
+    ///
+    /// A [cut](Error::is_cut) error always wins. Otherwise the error that got
+    /// further into the input is kept, since that alternative matched more,
+    /// and if both failed at the same point what they expected is merged. A
+    /// label is only kept if both errors have it.
+    #[must_use]
+    #[inline]
+    pub fn merge(self, other: Self) -> Self {
+        if self.cut || other.cut {
+            return if self.cut { self } else { other };
+        }
+        match self.remaining.cmp(&other.remaining) {
+            core::cmp::Ordering::Less => self,
+            core::cmp::Ordering::Greater => other,
+            core::cmp::Ordering::Equal => Self {
+                label: self.label.filter(|_| self.label == other.label),
+                expected: match (self.expected, other.expected) {
+                    (Some(first), Some(second)) => Some(first.or(second)),
+                    (first, second) => first.or(second),
+                },
+                ..self
+            },
+        }
+    }
+}
+
+/// Turns a failure into `None` so the caller can try something else, unless
+/// the failure is past a [`Parser::cut`].
+fn backtrack<T>(result: Result<T, Error>) -> Result<Option<T>, Error> {
+    match result {
+        Ok(parsed) => Ok(Some(parsed)),
+        Err(e) if e.is_cut() => Err(e),
+        Err(_) => Ok(None),
+    }
+}
 
 /// Main parsing function.
 ///
@@ -17,141 +331,1121 @@ pub struct Error;
 ///
 /// # Errors
 ///
-/// This function will return an error if parser will meet EOF.
+/// This function will return an error if parser will meet EOF or if any input
+/// is left unparsed. The error is located relative to `input`.
 pub fn parse<P: Parser>(mut parser: P, input: &str) -> Result<P::Output, Error> {
-    let (parsed, rest) = parser.parse(input)?;
+    new_session();
+    let (parsed, rest) = parser.parse(input).map_err(|e| e.locate(input))?;
     if rest.is_empty() {
         Ok(parsed)
     } else {
-        Err(Error)
+        Err(Error::new(rest).with_expected(Expected::End).locate(input))
+    }
+}
+
+/// Like [`parse`], but returns the unparsed rest of `input` instead of
+/// requiring the parser to consume all of it.
+///
+/// # Errors
+///
+/// This function will return an error if the parser fails. The error is
+/// located relative to `input`.
+pub fn parse_partial<P: Parser>(mut parser: P, input: &str) -> Result<(P::Output, &str), Error> {
+    new_session();
+    parser.parse(input).map_err(|e| e.locate(input))
+}
+
+/// Error of [`parse_reader`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadError {
+    /// Reading failed or the input wasn't valid UTF-8.
+    Io(io::Error),
+    Parse(Error),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "read error: {e}"),
+            Self::Parse(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Parse(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for ReadError {
+    fn from(e: Error) -> Self {
+        Self::Parse(e)
     }
 }
 
-pub trait Parser: Sized {
+#[cfg(feature = "std")]
+/// Like [`parse`], but reads the input from `reader`.
+///
+/// The reader is consumed to the end and buffered before parsing, so the way
+/// it splits the input into chunks doesn't matter, even if a chunk boundary
+/// falls inside a multi-byte character.
+///
+/// # Errors
+///
+/// This function will return [`ReadError::Io`] if reading fails or the input
+/// isn't valid UTF-8, and [`ReadError::Parse`] in the same cases as [`parse`].
+pub fn parse_reader<P: Parser, R: io::Read>(
+    parser: P,
+    mut reader: R,
+) -> Result<P::Output, ReadError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    Ok(parse(parser, &input)?)
+}
+
+/// Parsers keep no state between calls to [`Parser::parse`], so cloning one
+/// gives an independent parser that behaves exactly like the original, no
+/// matter how much the original has been used. Combinators are `Clone` when
+/// their parts are; a [`FromFn`] is only independent of its clones if its
+/// closure doesn't mutate what it captured.
+pub trait Parser {
     type Output;
 
     fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error>;
 
-    fn or<P: Parser>(self, parser: P) -> Or<Self, P> {
+    fn or<P: Parser>(self, parser: P) -> Or<Self, P>
+    where
+        Self: Sized,
+    {
         Or {
             first: self,
             second: parser,
         }
     }
 
+    /// Like [`Parser::or`] with three alternatives, without nesting
+    /// [`Either`]s.
+    fn or3<P, Q>(self, second: P, third: Q) -> Or3<Self, P, Q>
+    where
+        Self: Sized,
+        P: Parser,
+        Q: Parser,
+    {
+        Or3 {
+            first: self,
+            second,
+            third,
+        }
+    }
+
+    /// Like [`Parser::or`] with four alternatives, without nesting
+    /// [`Either`]s.
+    fn or4<P, Q, R>(self, second: P, third: Q, fourth: R) -> Or4<Self, P, Q, R>
+    where
+        Self: Sized,
+        P: Parser,
+        Q: Parser,
+        R: Parser,
+    {
+        Or4 {
+            first: self,
+            second,
+            third,
+            fourth,
+        }
+    }
+
+    fn or_same<P>(self, parser: P) -> OrSame<Self, P>
+    where
+        Self: Sized,
+        P: Parser<Output = Self::Output>,
+    {
+        OrSame {
+            first: self,
+            second: parser,
+        }
+    }
+
+    fn or_longest<P>(self, parser: P) -> OrLongest<Self, P>
+    where
+        Self: Sized,
+        P: Parser<Output = Self::Output>,
+    {
+        OrLongest {
+            first: self,
+            second: parser,
+        }
+    }
+
     fn map<F, T>(self, f: F) -> Map<Self, F>
     where
+        Self: Sized,
         F: FnMut(Self::Output) -> T,
     {
         Map { parser: self, f }
     }
 
+    fn map_err<F>(self, f: F) -> MapErr<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Error) -> Error,
+    {
+        MapErr { parser: self, f }
+    }
+
+    fn context(self, label: &'static str) -> Context<Self>
+    where
+        Self: Sized,
+    {
+        Context {
+            parser: self,
+            label,
+        }
+    }
+
     fn flat_map<F, P>(self, f: F) -> FlatMap<Self, F>
     where
+        Self: Sized,
         F: FnMut(Self::Output) -> P,
         P: Parser,
     {
         FlatMap { parser: self, f }
     }
 
-    fn zip_left<P>(self, parser: P) -> ZipLeft<Self, P> {
+    /// Runs the parser this parser outputs on the remaining input, like
+    /// [`Parser::flat_map`] without a function.
+    fn flatten(self) -> Flatten<Self>
+    where
+        Self: Sized,
+        Self::Output: Parser,
+    {
+        Flatten { parser: self }
+    }
+
+    fn zip_left<P>(self, parser: P) -> ZipLeft<Self, P>
+    where
+        Self: Sized,
+    {
         ZipLeft {
             left: self,
             right: parser,
         }
     }
-    fn zip_right<P>(self, parser: P) -> ZipRight<Self, P> {
+    fn zip_right<P>(self, parser: P) -> ZipRight<Self, P>
+    where
+        Self: Sized,
+    {
         ZipRight {
             left: self,
             right: parser,
         }
     }
 
-    fn until<P>(self, parser: P) -> Until<Self, P> {
+    fn and<P: Parser>(self, parser: P) -> And<Self, P>
+    where
+        Self: Sized,
+    {
+        And {
+            left: self,
+            right: parser,
+        }
+    }
+
+    fn until<P>(self, parser: P) -> Until<Self, P>
+    where
+        Self: Sized,
+    {
         Until {
             parser: self,
             until: parser,
         }
     }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Until<P, Q> {
-    parser: P,
-    until: Q,
-}
 
-impl<P, Q> Parser for Until<P, Q>
-where
-    P: Parser,
-    Q: Parser,
-{
-    type Output = Vec<P::Output>;
+    fn optional(self) -> Optional<Self>
+    where
+        Self: Sized,
+    {
+        Optional { parser: self }
+    }
 
-    fn parse<'s>(&mut self, mut input: &'s str) -> Result<(Self::Output, &'s str), Error> {
-        if input.is_empty() {
-            return Err(Error);
+    fn separated_by<S: Parser>(self, separator: S) -> SeparatedBy<Self, S>
+    where
+        Self: Sized,
+    {
+        SeparatedBy {
+            parser: self,
+            separator,
         }
+    }
 
-        let mut parsed = vec![];
-        while let Err(..) = self.until.parse(input) {
-            let (c, rest) = self.parser.parse(input)?;
-            parsed.push(c);
-            input = rest;
+    fn sep_end_by<S: Parser>(self, separator: S) -> SepEndBy<Self, S>
+    where
+        Self: Sized,
+    {
+        SepEndBy {
+            parser: self,
+            separator,
         }
-        Ok((parsed, input))
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ZipLeft<P, Q> {
-    left: P,
-    right: Q,
-}
+    fn peek(self) -> Peek<Self>
+    where
+        Self: Sized,
+    {
+        Peek { parser: self }
+    }
 
-impl<P, Q> Parser for ZipLeft<P, Q>
-where
-    P: Parser,
-    Q: Parser,
-{
-    type Output = P::Output;
+    fn recognize(self) -> Recognize<Self>
+    where
+        Self: Sized,
+    {
+        Recognize { parser: self }
+    }
 
-    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
-        let (parsed, rest) = self.left.parse(input)?;
-        let (_, rest) = self.right.parse(rest)?;
-        Ok((parsed, rest))
+    fn value<T: Clone>(self, value: T) -> Value<Self, T>
+    where
+        Self: Sized,
+    {
+        Value {
+            parser: self,
+            value,
+        }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct ZipRight<P, Q> {
-    left: P,
-    right: Q,
-}
+    fn or_else_value(self, default: Self::Output) -> OrElseValue<Self, Self::Output>
+    where
+        Self: Sized,
+        Self::Output: Clone,
+    {
+        OrElseValue {
+            parser: self,
+            default,
+        }
+    }
 
-impl<P, Q> Parser for ZipRight<P, Q>
-where
-    P: Parser,
-    Q: Parser,
-{
-    type Output = Q::Output;
+    fn verify<F>(self, pred: F) -> Verify<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Output) -> bool,
+    {
+        Verify { parser: self, pred }
+    }
 
-    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
-        let (_, rest) = self.left.parse(input)?;
-        let (parsed, rest) = self.right.parse(rest)?;
-        Ok((parsed, rest))
+    /// Pairs the output with the [`Span`] of input consumed. Call
+    /// [`Span::locate`] with the whole input to get byte offsets.
+    fn spanned(self) -> Spanned<Self>
+    where
+        Self: Sized,
+    {
+        Spanned { parser: self }
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct FlatMap<P, F> {
-    parser: P,
-    f: F,
-}
+    /// Like [`Parser::map`], but also passes `f` the [`Span`] consumed, as
+    /// [`Parser::spanned`] reports it.
+    fn map_with_span<F, T>(self, f: F) -> MapWithSpan<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Output, Span) -> T,
+    {
+        MapWithSpan { parser: self, f }
+    }
 
-impl<P, Q, F> Parser for FlatMap<P, F>
-where
-    P: Parser,
+    /// Like [`many`], but collects the outputs into any `C`.
+    fn collect<C>(self) -> Collect<Self, C>
+    where
+        Self: Sized,
+        C: FromIterator<Self::Output>,
+    {
+        Collect {
+            parser: self,
+            collection: PhantomData,
+        }
+    }
+
+    /// Prints every run of this parser to stderr, see [`Trace`].
+    #[cfg(feature = "std")]
+    fn debug(self, label: &'static str) -> Trace<Self, Stderr>
+    where
+        Self: Sized,
+    {
+        self.debug_to(label, Stderr)
+    }
+
+    /// Like [`Parser::debug`], but writes to `sink` instead of stderr.
+    fn debug_to<W: fmt::Write>(self, label: &'static str, sink: W) -> Trace<Self, W>
+    where
+        Self: Sized,
+    {
+        Trace {
+            parser: self,
+            label,
+            sink,
+        }
+    }
+
+    fn iter(self, input: &str) -> Iter<'_, Self>
+    where
+        Self: Sized,
+    {
+        Iter {
+            parser: self,
+            input,
+            done: false,
+        }
+    }
+
+    /// Commits to this parser: its errors propagate through [`Parser::or`],
+    /// [`Parser::optional`], [`many`] and the like instead of letting them
+    /// backtrack, so they aren't hidden behind a less relevant error. Use it
+    /// for what must follow once a construct has started, as in
+    /// `character('(').zip_right(body.cut())`.
+    fn cut(self) -> Cut<Self>
+    where
+        Self: Sized,
+    {
+        Cut { parser: self }
+    }
+
+    /// Calls `f` on each successful output and passes the output on
+    /// unchanged, for logging or counting matches.
+    fn inspect<F>(self, f: F) -> Inspect<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Output),
+    {
+        Inspect { parser: self, f }
+    }
+
+    /// Memoizes this parser: its result at each position is cached, so
+    /// parsing the same input again returns the cached result without
+    /// running the parser.
+    ///
+    /// Clones share the cache, so one fused parser can be used in several
+    /// branches of an [`Parser::or`] chain and still run once per position,
+    /// as in a packrat parser. Positions are told apart by the length of
+    /// input left, so the cache only lives as long as one input: it's emptied
+    /// by every call to [`parse`] or [`parse_partial`], and when the parser
+    /// sees an input that ends somewhere else. When calling
+    /// [`Parser::parse`] directly, call [`Fuse::clear`] before parsing a new
+    /// string that might reuse the memory of an old one.
+    fn fuse(self) -> Fuse<Self, Self::Output>
+    where
+        Self: Sized,
+        Self::Output: Clone,
+    {
+        Fuse {
+            parser: self,
+            cache: Rc::default(),
+        }
+    }
+
+    /// Skips the [`whitespace`] after this parser. To skip Lisp comments as
+    /// well, use [`token`](crate::lisp_comb::token).
+    fn then_ws(self) -> ThenWs<Self>
+    where
+        Self: Sized,
+    {
+        ThenWs { parser: self }
+    }
+
+    fn boxed<'a>(self) -> Box<dyn Parser<Output = Self::Output> + 'a>
+    where
+        Self: Sized + 'a,
+    {
+        Box::new(self)
+    }
+}
+
+/// Functions and closures with the right signature are parsers themselves.
+///
+/// Closure literals usually can't be inferred to return a slice of their
+/// argument, so wrap those in [`from_fn`] instead.
+impl<F, T> Parser for F
+where
+    F: FnMut(&str) -> Result<(T, &str), Error>,
+{
+    type Output = T;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        self(input)
+    }
+}
+
+impl<'a> Parser for &'a str {
+    type Output = &'a str;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        input
+            .strip_prefix(*self)
+            .map_or(Err(Error::new(input)), |rest| Ok((*self, rest)))
+    }
+}
+
+macro_rules! impl_tuple_parser {
+    ($($parser:ident $parsed:ident),+) => {
+        /// Runs the parsers in order and collects their outputs.
+        impl<$($parser: Parser),+> Parser for ($($parser,)+) {
+            type Output = ($($parser::Output,)+);
+
+            fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+                #[allow(non_snake_case)]
+                let ($($parser,)+) = self;
+                $(let ($parsed, input) = $parser.parse(input)?;)+
+                Ok((($($parsed,)+), input))
+            }
+        }
+    };
+}
+
+impl_tuple_parser!(A a, B b);
+impl_tuple_parser!(A a, B b, C c);
+impl_tuple_parser!(A a, B b, C c, D d);
+impl_tuple_parser!(A a, B b, C c, D d, E e);
+impl_tuple_parser!(A a, B b, C c, D d, E e, F f);
+
+impl<O> Parser for Box<dyn Parser<Output = O> + '_> {
+    type Output = O;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        (**self).parse(input)
+    }
+}
+
+/// Counts the calls to [`parse`] and [`parse_partial`], so a [`Fuse`] can
+/// tell when its cached results belong to an earlier input.
+static SESSION: AtomicUsize = AtomicUsize::new(0);
+
+/// Starts parsing a new input, invalidating the caches of all [`Fuse`]s.
+fn new_session() {
+    SESSION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Cached results of a [`Fuse`] for one input, by the length of input left
+/// at each position, with the length consumed on success.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FuseCache<T> {
+    /// The [`SESSION`] the results were cached in.
+    session: usize,
+    /// Address of the end of the input, which all its positions share.
+    end: usize,
+    results: BTreeMap<usize, Result<(T, usize), Error>>,
+}
+
+impl<T> Default for FuseCache<T> {
+    fn default() -> Self {
+        Self {
+            session: SESSION.load(Ordering::Relaxed),
+            end: 0,
+            results: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fuse<P, T> {
+    parser: P,
+    cache: Rc<RefCell<FuseCache<T>>>,
+}
+
+impl<P, T> Fuse<P, T> {
+    /// Forgets all cached results, including those seen by clones.
+    pub fn clear(&self) {
+        self.cache.borrow_mut().results.clear();
+    }
+}
+
+impl<P> Parser for Fuse<P, P::Output>
+where
+    P: Parser,
+    P::Output: Clone,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let cached = {
+            let mut cache = self.cache.borrow_mut();
+            let session = SESSION.load(Ordering::Relaxed);
+            let end = input.as_ptr() as usize + input.len();
+            if cache.session != session || cache.end != end {
+                cache.session = session;
+                cache.end = end;
+                cache.results.clear();
+            }
+            BTreeMap::get(&cache.results, &input.len()).cloned()
+        };
+        let result = if let Some(result) = cached {
+            result
+        } else {
+            let result = self
+                .parser
+                .parse(input)
+                .map(|(parsed, rest)| (parsed, input.len() - rest.len()));
+            let mut cache = self.cache.borrow_mut();
+            cache.results.insert(input.len(), result.clone());
+            result
+        };
+        result.map(|(parsed, consumed)| (parsed, &input[consumed..]))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThenWs<P> {
+    parser: P,
+}
+
+impl<P> Parser for ThenWs<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        let (_, rest) = skip_many(whitespace()).parse(rest)?;
+        Ok((parsed, rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspect<P, F> {
+    parser: P,
+    f: F,
+}
+
+impl<P, F> Parser for Inspect<P, F>
+where
+    P: Parser,
+    F: FnMut(&P::Output),
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        (self.f)(&parsed);
+        Ok((parsed, rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cut<P> {
+    parser: P,
+}
+
+impl<P> Parser for Cut<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        self.parser
+            .parse(input)
+            .map_err(|e| Error { cut: true, ..e })
+    }
+}
+
+/// Passes parsing through to the inner parser, writing a line to the sink
+/// with the label, the start of the input and the outcome each time.
+///
+/// Errors from the sink are ignored, so they never change the parse result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace<P, W> {
+    parser: P,
+    label: &'static str,
+    sink: W,
+}
+
+impl<P, W> Parser for Trace<P, W>
+where
+    P: Parser,
+    W: fmt::Write,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let result = self.parser.parse(input);
+        let _ = match &result {
+            Ok((_, rest)) => writeln!(
+                self.sink,
+                "{}: {:?} -> ok, remaining {:?}",
+                self.label,
+                preview(input),
+                preview(rest)
+            ),
+            Err(_) => writeln!(self.sink, "{}: {:?} -> error", self.label, preview(input)),
+        };
+        result
+    }
+}
+
+/// The first few characters of `input`, for [`Trace`] output.
+fn preview(input: &str) -> &str {
+    input
+        .char_indices()
+        .nth(16)
+        .map_or(input, |(i, _)| &input[..i])
+}
+
+/// [`fmt::Write`] sink for [`Parser::debug`] that prints to stderr.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stderr;
+
+#[cfg(feature = "std")]
+impl fmt::Write for Stderr {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        eprint!("{s}");
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collect<P, C> {
+    parser: P,
+    collection: PhantomData<fn() -> C>,
+}
+
+impl<P, C> Parser for Collect<P, C>
+where
+    P: Parser,
+    C: FromIterator<P::Output>,
+{
+    type Output = C;
+
+    fn parse<'s>(&mut self, mut input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let mut error = None;
+        let collection = core::iter::from_fn(|| match backtrack(self.parser.parse(input)) {
+            Ok(parsed) => {
+                let (parsed, rest) = parsed?;
+                input = rest;
+                Some(parsed)
+            }
+            Err(e) => {
+                error = Some(e);
+                None
+            }
+        })
+        .collect();
+        match error {
+            Some(e) => Err(e),
+            None => Ok((collection, input)),
+        }
+    }
+}
+
+/// Iterator that runs the parser repeatedly over `input`, see [`Parser::iter`].
+///
+/// Iteration stops once the parser fails. A parser that succeeds without
+/// consuming anything yields an error instead of looping forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Iter<'s, P> {
+    parser: P,
+    input: &'s str,
+    done: bool,
+}
+
+impl<'s, P> Iter<'s, P> {
+    /// Returns the input that hasn't been parsed yet.
+    pub fn remaining(&self) -> &'s str {
+        self.input
+    }
+}
+
+impl<P> Iterator for Iter<'_, P>
+where
+    P: Parser,
+{
+    type Item = Result<P::Output, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (parsed, rest) = match self.parser.parse(self.input) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.done = true;
+                return e.is_cut().then_some(Err(e));
+            }
+        };
+        if rest.len() == self.input.len() {
+            self.done = true;
+            return Some(Err(Error::new(self.input)));
+        }
+        self.input = rest;
+        Some(Ok(parsed))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapWithSpan<P, F> {
+    parser: P,
+    f: F,
+}
+
+impl<P, F, T> Parser for MapWithSpan<P, F>
+where
+    P: Parser,
+    F: FnMut(P::Output, Span) -> T,
+{
+    type Output = T;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        Ok(((self.f)(parsed, Span::new(input, rest)), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<P> {
+    parser: P,
+}
+
+impl<P> Parser for Spanned<P>
+where
+    P: Parser,
+{
+    type Output = (P::Output, Span);
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        Ok(((parsed, Span::new(input, rest)), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verify<P, F> {
+    parser: P,
+    pred: F,
+}
+
+impl<P, F> Parser for Verify<P, F>
+where
+    P: Parser,
+    F: FnMut(&P::Output) -> bool,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        if (self.pred)(&parsed) {
+            Ok((parsed, rest))
+        } else {
+            Err(Error::new(input))
+        }
+    }
+}
+
+/// Falls back to a default value, consuming nothing, if the inner parser fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrElseValue<P, T> {
+    parser: P,
+    default: T,
+}
+
+impl<P> Parser for OrElseValue<P, P::Output>
+where
+    P: Parser,
+    P::Output: Clone,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        match backtrack(self.parser.parse(input))? {
+            Some(parsed) => Ok(parsed),
+            None => Ok((self.default.clone(), input)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Value<P, T> {
+    parser: P,
+    value: T,
+}
+
+impl<P, T> Parser for Value<P, T>
+where
+    P: Parser,
+    T: Clone,
+{
+    type Output = T;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (_, rest) = self.parser.parse(input)?;
+        Ok((self.value.clone(), rest))
+    }
+}
+
+/// Returns the [`Span`] of input consumed by the inner parser instead of its
+/// output.
+///
+/// The output can't be the consumed `&'s str` itself: [`Parser::Output`] is
+/// one type for every input lifetime `'s` that [`Parser::parse`] is called
+/// with, so it can't name any of them. [`Span::slice`] turns the span into the
+/// slice without copying, and [`Recognize::parse_slice`] does both at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recognize<P> {
+    parser: P,
+}
+
+impl<P: Parser> Recognize<P> {
+    /// Like [`Parser::parse`], but returns the consumed part of `input`
+    /// instead of its [`Span`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the inner parser fails.
+    pub fn parse_slice<'s>(&mut self, input: &'s str) -> Result<(&'s str, &'s str), Error> {
+        let (_, rest) = self.parser.parse(input)?;
+        Ok((Span::new(input, rest).slice(input), rest))
+    }
+}
+
+impl<P> Parser for Recognize<P>
+where
+    P: Parser,
+{
+    type Output = Span;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (_, rest) = self.parser.parse(input)?;
+        Ok((Span::new(input, rest), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SepEndBy<P, S> {
+    parser: P,
+    separator: S,
+}
+
+impl<P, S> Parser for SepEndBy<P, S>
+where
+    P: Parser,
+    S: Parser,
+{
+    type Output = Vec<P::Output>;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let Some((first, mut input)) = backtrack(self.parser.parse(input))? else {
+            return Ok((vec![], input));
+        };
+
+        let mut parsed = vec![first];
+        while let Some((_, rest)) = backtrack(self.separator.parse(input))? {
+            input = rest;
+            let Some((item, rest)) = backtrack(self.parser.parse(input))? else {
+                break;
+            };
+            parsed.push(item);
+            input = rest;
+        }
+        Ok((parsed, input))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Peek<P> {
+    parser: P,
+}
+
+impl<P> Parser for Peek<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        match self.parser.parse(input) {
+            Ok((parsed, _)) => Ok((parsed, input)),
+            Err(e) if e.is_cut() => Err(e),
+            // What a lookahead expected isn't what the input is missing.
+            Err(_) => Err(Error::new(input)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeparatedBy<P, S> {
+    parser: P,
+    separator: S,
+}
+
+impl<P, S> Parser for SeparatedBy<P, S>
+where
+    P: Parser,
+    S: Parser,
+{
+    type Output = Vec<P::Output>;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let Some((first, mut input)) = backtrack(self.parser.parse(input))? else {
+            return Ok((vec![], input));
+        };
+
+        let mut parsed = vec![first];
+        while let Some((_, rest)) = backtrack(self.separator.parse(input))? {
+            let Some((item, rest)) = backtrack(self.parser.parse(rest))? else {
+                break;
+            };
+            parsed.push(item);
+            input = rest;
+        }
+        Ok((parsed, input))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Optional<P> {
+    parser: P,
+}
+
+impl<P> Parser for Optional<P>
+where
+    P: Parser,
+{
+    type Output = Option<P::Output>;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        match self.parser.parse(input) {
+            Ok((parsed, rest)) => Ok((Some(parsed), rest)),
+            Err(e) if e.is_cut() => Err(e),
+            Err(..) => Ok((None, input)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Until<P, Q> {
+    parser: P,
+    until: Q,
+}
+
+impl<P, Q> Parser for Until<P, Q>
+where
+    P: Parser,
+    Q: Parser,
+{
+    type Output = Vec<P::Output>;
+
+    fn parse<'s>(&mut self, mut input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let mut parsed = vec![];
+        while backtrack(self.until.parse(input))?.is_none() {
+            let (c, rest) = self.parser.parse(input)?;
+            // An item that consumes nothing would never reach the terminator.
+            if rest.len() == input.len() {
+                return Err(Error::new(input));
+            }
+            parsed.push(c);
+            input = rest;
+        }
+        Ok((parsed, input))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct And<P, Q> {
+    left: P,
+    right: Q,
+}
+
+impl<P, Q> Parser for And<P, Q>
+where
+    P: Parser,
+    Q: Parser,
+{
+    type Output = (P::Output, Q::Output);
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (left, rest) = self.left.parse(input)?;
+        let (right, rest) = self.right.parse(rest)?;
+        Ok(((left, right), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipLeft<P, Q> {
+    left: P,
+    right: Q,
+}
+
+impl<P, Q> Parser for ZipLeft<P, Q>
+where
+    P: Parser,
+    Q: Parser,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.left.parse(input)?;
+        let (_, rest) = self.right.parse(rest)?;
+        Ok((parsed, rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipRight<P, Q> {
+    left: P,
+    right: Q,
+}
+
+impl<P, Q> Parser for ZipRight<P, Q>
+where
+    P: Parser,
+    Q: Parser,
+{
+    type Output = Q::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (_, rest) = self.left.parse(input)?;
+        let (parsed, rest) = self.right.parse(rest)?;
+        Ok((parsed, rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flatten<P> {
+    parser: P,
+}
+
+impl<P> Parser for Flatten<P>
+where
+    P: Parser,
+    P::Output: Parser,
+{
+    type Output = <P::Output as Parser>::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (mut parser, rest) = self.parser.parse(input)?;
+        parser.parse(rest)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlatMap<P, F> {
+    parser: P,
+    f: F,
+}
+
+impl<P, Q, F> Parser for FlatMap<P, F>
+where
+    P: Parser,
     F: FnMut(P::Output) -> Q,
     Q: Parser,
 {
@@ -185,48 +1479,254 @@ pub struct Map<P, F> {
     f: F,
 }
 
-impl<P, F, T> Parser for Map<P, F>
+impl<P, F, T> Parser for Map<P, F>
+where
+    P: Parser,
+    F: FnMut(P::Output) -> T,
+{
+    type Output = T;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        Ok(((self.f)(parsed), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapErr<P, F> {
+    parser: P,
+    f: F,
+}
+
+impl<P, F> Parser for MapErr<P, F>
+where
+    P: Parser,
+    F: FnMut(Error) -> Error,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        self.parser.parse(input).map_err(&mut self.f)
+    }
+}
+
+/// Labels errors of the inner parser. Labels of inner contexts are replaced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Context<P> {
+    parser: P,
+    label: &'static str,
+}
+
+impl<P> Parser for Context<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        self.parser
+            .parse(input)
+            .map_err(|e| e.with_label(self.label))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Or<P, Q> {
+    first: P,
+    second: Q,
+}
+
+impl<P, Q> Parser for Or<P, Q>
+where
+    P: Parser,
+    Q: Parser,
+{
+    type Output = Either<P::Output, Q::Output>;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let error = match self.first.parse(input) {
+            Ok((parsed, rest)) => return Ok((Either::A(parsed), rest)),
+            Err(e) if e.is_cut() => return Err(e),
+            Err(e) => e,
+        };
+        let (parsed, rest) = self.second.parse(input).map_err(|e| error.merge(e))?;
+        Ok((Either::B(parsed), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Or3<P, Q, R> {
+    first: P,
+    second: Q,
+    third: R,
+}
+
+impl<P, Q, R> Parser for Or3<P, Q, R>
+where
+    P: Parser,
+    Q: Parser,
+    R: Parser,
+{
+    type Output = Either3<P::Output, Q::Output, R::Output>;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let error = match self.first.parse(input) {
+            Ok((parsed, rest)) => return Ok((Either3::A(parsed), rest)),
+            Err(e) if e.is_cut() => return Err(e),
+            Err(e) => e,
+        };
+        let error = match self.second.parse(input) {
+            Ok((parsed, rest)) => return Ok((Either3::B(parsed), rest)),
+            Err(e) if e.is_cut() => return Err(e),
+            Err(e) => error.merge(e),
+        };
+        let (parsed, rest) = self.third.parse(input).map_err(|e| error.merge(e))?;
+        Ok((Either3::C(parsed), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Or4<P, Q, R, S> {
+    first: P,
+    second: Q,
+    third: R,
+    fourth: S,
+}
+
+impl<P, Q, R, S> Parser for Or4<P, Q, R, S>
+where
+    P: Parser,
+    Q: Parser,
+    R: Parser,
+    S: Parser,
+{
+    type Output = Either4<P::Output, Q::Output, R::Output, S::Output>;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        let error = match self.first.parse(input) {
+            Ok((parsed, rest)) => return Ok((Either4::A(parsed), rest)),
+            Err(e) if e.is_cut() => return Err(e),
+            Err(e) => e,
+        };
+        let error = match self.second.parse(input) {
+            Ok((parsed, rest)) => return Ok((Either4::B(parsed), rest)),
+            Err(e) if e.is_cut() => return Err(e),
+            Err(e) => error.merge(e),
+        };
+        let error = match self.third.parse(input) {
+            Ok((parsed, rest)) => return Ok((Either4::C(parsed), rest)),
+            Err(e) if e.is_cut() => return Err(e),
+            Err(e) => error.merge(e),
+        };
+        let (parsed, rest) = self.fourth.parse(input).map_err(|e| error.merge(e))?;
+        Ok((Either4::D(parsed), rest))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrSame<P, Q> {
+    first: P,
+    second: Q,
+}
+
+impl<P, Q> Parser for OrSame<P, Q>
 where
     P: Parser,
-    F: FnMut(P::Output) -> T,
+    Q: Parser<Output = P::Output>,
 {
-    type Output = T;
+    type Output = P::Output;
 
     fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
-        let (parsed, rest) = self.parser.parse(input)?;
-        Ok(((self.f)(parsed), rest))
+        self.first.parse(input).or_else(|e| {
+            if e.is_cut() {
+                Err(e)
+            } else {
+                self.second.parse(input).map_err(|second| e.merge(second))
+            }
+        })
     }
 }
 
+/// Runs both parsers and keeps the result that consumed more input,
+/// preferring the first one on a tie.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Or<P, Q> {
+pub struct OrLongest<P, Q> {
     first: P,
     second: Q,
 }
 
-impl<P, Q> Parser for Or<P, Q>
+impl<P, Q> Parser for OrLongest<P, Q>
 where
     P: Parser,
-    Q: Parser,
+    Q: Parser<Output = P::Output>,
 {
-    type Output = Either<P::Output, Q::Output>;
+    type Output = P::Output;
 
     fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
-        if let Ok((parsed, rest)) = self.first.parse(input) {
-            Ok((Either::A(parsed), rest))
-        } else {
-            let (parsed, rest) = self.second.parse(input)?;
-            Ok((Either::B(parsed), rest))
+        match (self.first.parse(input), self.second.parse(input)) {
+            (Err(e), _) | (_, Err(e)) if e.is_cut() => Err(e),
+            (Ok(first), Ok(second)) if second.1.len() < first.1.len() => Ok(second),
+            (Ok(first), _) => Ok(first),
+            (Err(_), Ok(second)) => Ok(second),
+            (Err(first), Err(second)) => Err(first.merge(second)),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Either<A, B> {
     A(A),
     B(B),
 }
 
+impl<A, B> Either<A, B> {
+    pub fn left(self) -> Option<A> {
+        match self {
+            Either::A(a) => Some(a),
+            Either::B(_) => None,
+        }
+    }
+
+    pub fn right(self) -> Option<B> {
+        match self {
+            Either::A(_) => None,
+            Either::B(b) => Some(b),
+        }
+    }
+
+    pub fn map_left<F, T>(self, f: F) -> Either<T, B>
+    where
+        F: FnOnce(A) -> T,
+    {
+        match self {
+            Either::A(a) => Either::A(f(a)),
+            Either::B(b) => Either::B(b),
+        }
+    }
+
+    pub fn map_right<F, T>(self, f: F) -> Either<A, T>
+    where
+        F: FnOnce(B) -> T,
+    {
+        match self {
+            Either::A(a) => Either::A(a),
+            Either::B(b) => Either::B(f(b)),
+        }
+    }
+
+    /// Converts [`Either::A`] into [`Ok`] and [`Either::B`] into [`Err`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the value of [`Either::B`] as the error.
+    pub fn into_result(self) -> Result<A, B> {
+        match self {
+            Either::A(a) => Ok(a),
+            Either::B(b) => Err(b),
+        }
+    }
+}
+
 pub trait Get<T> {
     fn get(self) -> T;
 }
@@ -251,84 +1751,528 @@ where
     }
 }
 
+/// Output of [`Parser::or3`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Either3<A, B, C> {
+    A(A),
+    B(B),
+    C(C),
+}
+
+impl<A, B, C> Get<C> for Either3<A, B, C>
+where
+    A: Get<C>,
+    B: Get<C>,
+    C: Get<C>,
+{
+    #[inline]
+    fn get(self) -> C {
+        match self {
+            Either3::A(a) => a.get(),
+            Either3::B(b) => b.get(),
+            Either3::C(c) => c.get(),
+        }
+    }
+}
+
+/// Output of [`Parser::or4`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Either4<A, B, C, D> {
+    A(A),
+    B(B),
+    C(C),
+    D(D),
+}
+
+impl<A, B, C, D> Get<D> for Either4<A, B, C, D>
+where
+    A: Get<D>,
+    B: Get<D>,
+    C: Get<D>,
+    D: Get<D>,
+{
+    #[inline]
+    fn get(self) -> D {
+        match self {
+            Either4::A(a) => a.get(),
+            Either4::B(b) => b.get(),
+            Either4::C(c) => c.get(),
+            Either4::D(d) => d.get(),
+        }
+    }
+}
+
 #[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn character(c: char) -> impl Parser<Output = char> {
+pub fn choice<O>(mut parsers: Vec<Box<dyn Parser<Output = O>>>) -> impl Parser<Output = O> {
     from_fn(move |input| {
-        input.chars().next().map_or(Err(Error), |ch| {
-            if ch == c {
-                Ok((c, &input[1..]))
-            } else {
-                Err(Error)
+        let mut error: Option<Error> = None;
+        for parser in &mut parsers {
+            match parser.parse(input) {
+                Ok(ok) => return Ok(ok),
+                Err(e) if e.is_cut() => return Err(e),
+                Err(e) => {
+                    error = Some(match error.take() {
+                        Some(error) => error.merge(e),
+                        None => e,
+                    });
+                }
             }
-        })
+        }
+        Err(error.unwrap_or_else(|| Error::new(input)))
+    })
+}
+
+/// Parser that can return every way it matches, for debugging an ambiguous
+/// grammar.
+///
+/// Alternatives return the matches of all their branches instead of only the
+/// first, and sequences run what follows on the rest of every match of what
+/// precedes it, so ambiguity nested anywhere in the grammar shows up. Other
+/// parsers can be used as parts that match at most once by wrapping them in
+/// [`unambiguous`].
+pub trait ParseAll: Parser {
+    /// Returns every successful parse at the start of `input`, in the order
+    /// [`Parser::parse`] prefers them, so the first is the one it returns.
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)>;
+}
+
+/// Wraps `parser` to use it in a [`ParseAll`] grammar, as a part with at most
+/// one match.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn unambiguous<P: Parser>(parser: P) -> Unambiguous<P> {
+    Unambiguous { parser }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unambiguous<P> {
+    parser: P,
+}
+
+impl<P: Parser> Parser for Unambiguous<P> {
+    type Output = P::Output;
+
+    fn parse<'s>(&mut self, input: &'s str) -> Result<(Self::Output, &'s str), Error> {
+        self.parser.parse(input)
+    }
+}
+
+impl<P: Parser> ParseAll for Unambiguous<P> {
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        self.parser.parse(input).into_iter().collect()
+    }
+}
+
+impl<P, Q> ParseAll for Or<P, Q>
+where
+    P: ParseAll,
+    Q: ParseAll,
+{
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        let first = self.first.parse_all(input).into_iter();
+        let second = self.second.parse_all(input).into_iter();
+        first
+            .map(|(parsed, rest)| (Either::A(parsed), rest))
+            .chain(second.map(|(parsed, rest)| (Either::B(parsed), rest)))
+            .collect()
+    }
+}
+
+impl<P, Q> ParseAll for OrSame<P, Q>
+where
+    P: ParseAll,
+    Q: ParseAll<Output = P::Output>,
+{
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        let mut parses = self.first.parse_all(input);
+        parses.extend(self.second.parse_all(input));
+        parses
+    }
+}
+
+impl<P, F, T> ParseAll for Map<P, F>
+where
+    P: ParseAll,
+    F: FnMut(P::Output) -> T,
+{
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        let parses = self.parser.parse_all(input).into_iter();
+        parses
+            .map(|(parsed, rest)| ((self.f)(parsed), rest))
+            .collect()
+    }
+}
+
+impl<A, B> ParseAll for (A, B)
+where
+    A: ParseAll,
+    A::Output: Clone,
+    B: ParseAll,
+{
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        let (first, second) = self;
+        let mut parses = vec![];
+        for (a, rest) in first.parse_all(input) {
+            let after = second.parse_all(rest).into_iter();
+            parses.extend(after.map(|(b, rest)| ((a.clone(), b), rest)));
+        }
+        parses
+    }
+}
+
+impl<P, Q> ParseAll for ZipLeft<P, Q>
+where
+    P: ParseAll,
+    P::Output: Clone,
+    Q: ParseAll,
+{
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        let mut parses = vec![];
+        for (parsed, rest) in self.left.parse_all(input) {
+            let after = self.right.parse_all(rest).into_iter();
+            parses.extend(after.map(|(_, rest)| (parsed.clone(), rest)));
+        }
+        parses
+    }
+}
+
+impl<P, Q> ParseAll for ZipRight<P, Q>
+where
+    P: ParseAll,
+    Q: ParseAll,
+{
+    fn parse_all<'s>(&mut self, input: &'s str) -> Vec<(Self::Output, &'s str)> {
+        let mut parses = vec![];
+        for (_, rest) in self.left.parse_all(input) {
+            parses.extend(self.right.parse_all(rest));
+        }
+        parses
+    }
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn tag(tag: &str) -> impl Parser<Output = &str> + Clone {
+    tag
+}
+
+/// Splits the first char off `input`.
+#[inline]
+fn split_first_char(input: &str) -> Option<(char, &str)> {
+    let mut chars = input.chars();
+    let c = chars.next()?;
+    Some((c, chars.as_str()))
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn character(c: char) -> impl Parser<Output = char> + Clone {
+    from_fn(move |input| match split_first_char(input) {
+        Some((ch, rest)) if ch == c => Ok((c, rest)),
+        _ => Err(Error::new(input).with_expected(Expected::Char(c))),
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn preceded<P: Parser, Q: Parser>(prefix: P, parser: Q) -> impl Parser<Output = Q::Output> {
+    prefix.zip_right(parser)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn terminated<P: Parser, Q: Parser>(parser: P, suffix: Q) -> impl Parser<Output = P::Output> {
+    parser.zip_left(suffix)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn delimited<L, M, R>(left: L, middle: M, right: R) -> impl Parser<Output = M::Output>
+where
+    L: Parser,
+    M: Parser,
+    R: Parser,
+{
+    preceded(left, terminated(middle, right))
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn many<P: Parser>(mut parser: P) -> impl Parser<Output = Vec<P::Output>> {
+    from_fn(move |mut input| {
+        // if input.is_empty() {
+        //     return Err(Error::new(input));
+        // }
+
+        let mut parsed = vec![];
+        while let Some((ch, rest)) = backtrack(parser.parse(input))? {
+            parsed.push(ch);
+            input = rest;
+        }
+        Ok((parsed, input))
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn many1<P: Parser>(mut parser: P) -> impl Parser<Output = Vec<P::Output>> {
+    from_fn(move |input| {
+        let (first, mut input) = parser.parse(input)?;
+
+        let mut parsed = vec![first];
+        while let Some((ch, rest)) = backtrack(parser.parse(input))? {
+            parsed.push(ch);
+            input = rest;
+        }
+        Ok((parsed, input))
+    })
+}
+
+/// Collects `item`s until `end` matches, then consumes `end` too.
+///
+/// Unlike [`Parser::until`], the terminator's output is returned with the items.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn many_till<P, Q>(mut item: P, mut end: Q) -> impl Parser<Output = (Vec<P::Output>, Q::Output)>
+where
+    P: Parser,
+    Q: Parser,
+{
+    from_fn(move |mut input| {
+        let mut parsed = vec![];
+        loop {
+            if let Some((terminator, rest)) = backtrack(end.parse(input))? {
+                return Ok(((parsed, terminator), rest));
+            }
+            let (c, rest) = item.parse(input)?;
+            if rest.len() == input.len() {
+                return Err(Error::new(input));
+            }
+            parsed.push(c);
+            input = rest;
+        }
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn fold_many<P, A, F>(mut parser: P, init: A, mut f: F) -> impl Parser<Output = A>
+where
+    P: Parser,
+    A: Clone,
+    F: FnMut(A, P::Output) -> A,
+{
+    from_fn(move |mut input| {
+        let mut acc = init.clone();
+        while let Some((parsed, rest)) = backtrack(parser.parse(input))? {
+            acc = f(acc, parsed);
+            input = rest;
+        }
+        Ok((acc, input))
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn skip_many<P: Parser>(parser: P) -> impl Parser<Output = ()> {
+    fold_many(parser, (), |(), _| ())
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn count_matches<P: Parser>(parser: P) -> impl Parser<Output = usize> {
+    fold_many(parser, 0, |n, _| n + 1)
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn count<P: Parser>(n: usize, mut parser: P) -> impl Parser<Output = Vec<P::Output>> {
+    from_fn(move |mut input| {
+        let mut parsed = Vec::with_capacity(n);
+        for _ in 0..n {
+            let (item, rest) = parser.parse(input)?;
+            parsed.push(item);
+            input = rest;
+        }
+        Ok((parsed, input))
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn repeat_range<P: Parser>(
+    range: RangeInclusive<usize>,
+    mut parser: P,
+) -> impl Parser<Output = Vec<P::Output>> {
+    from_fn(move |mut input| {
+        let mut parsed = Vec::with_capacity(*range.start());
+        while parsed.len() < *range.end() {
+            match parser.parse(input) {
+                Ok((item, rest)) => {
+                    parsed.push(item);
+                    input = rest;
+                }
+                Err(e) if e.is_cut() || parsed.len() < *range.start() => return Err(e),
+                Err(..) => break,
+            }
+        }
+        Ok((parsed, input))
+    })
+}
+
+/// One or more `operand`s separated by `op`s, combined left to right with
+/// the functions the `op`s return, so `1-2-3` is `(1-2)-3`.
+///
+/// An `op` that isn't followed by an operand is left unconsumed.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn chainl1<P, O, F>(mut operand: P, mut op: O) -> impl Parser<Output = P::Output>
+where
+    P: Parser,
+    O: Parser<Output = F>,
+    F: FnMut(P::Output, P::Output) -> P::Output,
+{
+    from_fn(move |input| {
+        let (mut acc, mut input) = operand.parse(input)?;
+        while let Some((mut f, rest)) = backtrack(op.parse(input))? {
+            let Some((rhs, rest)) = backtrack(operand.parse(rest))? else {
+                break;
+            };
+            acc = f(acc, rhs);
+            input = rest;
+        }
+        Ok((acc, input))
+    })
+}
+
+/// Like [`chainl1`], but combines right to left, so `2^3^2` is `2^(3^2)`.
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn chainr1<P, O, F>(mut operand: P, mut op: O) -> impl Parser<Output = P::Output>
+where
+    P: Parser,
+    O: Parser<Output = F>,
+    F: FnMut(P::Output, P::Output) -> P::Output,
+{
+    from_fn(move |input| {
+        let (first, mut input) = operand.parse(input)?;
+        let mut operations = vec![];
+        while let Some((f, rest)) = backtrack(op.parse(input))? {
+            let Some((rhs, rest)) = backtrack(operand.parse(rest))? else {
+                break;
+            };
+            operations.push((f, rhs));
+            input = rest;
+        }
+
+        let Some((mut f, mut acc)) = operations.pop() else {
+            return Ok((first, input));
+        };
+        while let Some((g, lhs)) = operations.pop() {
+            acc = f(lhs, acc);
+            f = g;
+        }
+        Ok((f(first, acc), input))
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn not<P: Parser>(mut parser: P) -> impl Parser<Output = ()> {
+    from_fn(move |input| match parser.parse(input) {
+        Ok(..) => Err(Error::new(input)),
+        Err(e) if e.is_cut() => Err(e),
+        Err(..) => Ok(((), input)),
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn any() -> impl Parser<Output = char> + Clone {
+    from_fn(|input| split_first_char(input).ok_or_else(|| Error::new(input)))
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn whitespace() -> impl Parser<Output = ()> + Clone {
+    const WHITESPACE: &str = " \n\t\r";
+    from_fn(|input: &str| match split_first_char(input) {
+        Some((c, rest)) if WHITESPACE.contains(c) => Ok(((), rest)),
+        _ => Err(Error::new(input).with_expected(Expected::OneOf(WHITESPACE.into()))),
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn one_of(chars: &'static str) -> impl Parser<Output = char> + Clone {
+    from_fn(move |input| {
+        let error = || Error::new(input).with_expected(Expected::OneOf(chars.into()));
+        if chars.is_empty() {
+            return Err(error());
+        }
+
+        match split_first_char(input) {
+            Some((c, rest)) if chars.contains(c) => Ok((c, rest)),
+            _ => Err(error()),
+        }
     })
 }
 
 #[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn many<P: Parser>(mut parser: P) -> impl Parser<Output = Vec<P::Output>> {
-    from_fn(move |mut input| {
-        // if input.is_empty() {
-        //     return Err(Error);
-        // }
-
-        let mut parsed = vec![];
-        while let Ok((ch, rest)) = parser.parse(input) {
-            parsed.push(ch);
-            input = rest;
-        }
-        Ok((parsed, input))
+pub fn none_of(chars: &'static str) -> impl Parser<Output = char> + Clone {
+    from_fn(move |input| match split_first_char(input) {
+        Some((c, rest)) if !chars.contains(c) => Ok((c, rest)),
+        _ => Err(Error::new(input).with_expected(Expected::NoneOf(chars.into()))),
     })
 }
 
 #[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn any() -> impl Parser<Output = char> {
-    from_fn(|input| {
-        input
-            .chars()
-            .next()
-            .map_or(Err(Error), |c| Ok((c, &input[1..])))
+pub fn satisfy<F: FnMut(char) -> bool>(mut pred: F) -> impl Parser<Output = char> {
+    from_fn(move |input: &str| match split_first_char(input) {
+        Some((c, rest)) if pred(c) => Ok((c, rest)),
+        _ => Err(Error::new(input).with_expected(Expected::Satisfy)),
     })
 }
 
 #[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn whitespace() -> impl Parser<Output = ()> {
-    character(' ')
-        .or(character('\n'))
-        .or(character('\t'))
-        .map(|_| ())
+pub fn eof() -> impl Parser<Output = ()> + Clone {
+    from_fn(|input: &str| {
+        if input.is_empty() {
+            Ok(((), input))
+        } else {
+            Err(Error::new(input).with_expected(Expected::End))
+        }
+    })
 }
 
 #[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn one_of(chars: &str) -> impl Parser<Output = char> + '_ {
+pub fn range(r: RangeInclusive<char>) -> impl Parser<Output = char> + Clone {
     from_fn(move |input| {
-        if chars.is_empty() {
-            return Err(Error);
+        let error = || Error::new(input).with_expected(Expected::Range(r.clone()));
+        if r.is_empty() {
+            return Err(error());
         }
 
-        input.chars().next().map_or(Err(Error), |c| {
-            if chars.contains(c) {
-                Ok((c, &input[1..]))
-            } else {
-                Err(Error)
-            }
-        })
+        match split_first_char(input) {
+            Some((c, rest)) if r.contains(&c) => Ok((c, rest)),
+            _ => Err(error()),
+        }
+    })
+}
+
+#[must_use = "parsers do nothing unless passed to [`parse`]"]
+pub fn take_while<F: FnMut(char) -> bool>(mut pred: F) -> impl Parser<Output = String> {
+    from_fn(move |input: &str| {
+        // Scan ASCII bytes directly, and only decode from the first non-ASCII
+        // char on.
+        let ascii_end = input
+            .bytes()
+            .position(|b| !b.is_ascii() || !pred(char::from(b)))
+            .unwrap_or(input.len());
+        let end = if input
+            .as_bytes()
+            .get(ascii_end)
+            .is_some_and(|b| !b.is_ascii())
+        {
+            let tail = &input[ascii_end..];
+            ascii_end + tail.find(|c| !pred(c)).unwrap_or(tail.len())
+        } else {
+            ascii_end
+        };
+        Ok((input[..end].to_owned(), &input[end..]))
     })
 }
 
 #[must_use = "parsers do nothing unless passed to [`parse`]"]
-pub fn range(r: RangeInclusive<char>) -> impl Parser<Output = char> {
+pub fn take_while1<F: FnMut(char) -> bool>(pred: F) -> impl Parser<Output = String> {
+    let mut parser = take_while(pred);
     from_fn(move |input| {
-        if r.is_empty() {
-            return Err(Error);
+        let (parsed, rest) = parser.parse(input)?;
+        if parsed.is_empty() {
+            Err(Error::new(input))
+        } else {
+            Ok((parsed, rest))
         }
-
-        input.chars().next().map_or(Err(Error), |c| {
-            if r.contains(&c) {
-                Ok((c, &input[1..]))
-            } else {
-                Err(Error)
-            }
-        })
     })
 }
 
@@ -358,6 +2302,11 @@ where
 
 #[cfg(test)]
 mod tests {
+    use crate::{
+        lisp_comb::{float, ident, lisp_ident, lisp_list, lisp_object, lisp_string, number},
+        LispObject,
+    };
+
     use super::*;
 
     #[test]
@@ -366,7 +2315,249 @@ mod tests {
         let (parsed, rest) = parser.parse("hello!").unwrap();
         assert_eq!(parsed, &['h', 'e', 'l', 'l', 'o']);
         assert_eq!(rest, "!");
-        assert_eq!(Err(Error), parser.parse(""));
+        assert!(parser.parse("").is_err());
+        assert_eq!(Ok((vec![], "!")), parser.parse("!"));
+
+        let mut parser = any().until(eof());
+        assert_eq!(Ok((vec![], "")), parser.parse(""));
+        assert_eq!(Ok((vec!['a', 'b'], "")), parser.parse("ab"));
+
+        assert!(character('a').optional().until(eof()).parse("b").is_err());
+    }
+
+    #[test]
+    pub fn test_optional() {
+        let mut parser = character('a').optional();
+
+        let (parsed, rest) = parser.parse("ab").unwrap();
+        assert_eq!(parsed, Some('a'));
+        assert_eq!(rest, "b");
+
+        let (parsed, rest) = parser.parse("ba").unwrap();
+        assert_eq!(parsed, None);
+        assert_eq!(rest, "ba");
+
+        assert_eq!(Ok((None, "")), parser.parse(""));
+    }
+
+    #[test]
+    pub fn test_separated_by() {
+        let mut parser = number().separated_by(character(','));
+
+        let (parsed, rest) = parser.parse("1,2,3").unwrap();
+        assert_eq!(parsed, &[1, 2, 3]);
+        assert_eq!(rest, "");
+
+        assert_eq!(Ok((vec![], "")), parser.parse(""));
+        assert_eq!(Ok((vec![1], ",")), parser.parse("1,"));
+        assert_eq!(Ok((vec![1, 2], ",x")), parser.parse("1,2,x"));
+    }
+
+    #[test]
+    pub fn test_sep_end_by() {
+        let mut parser = number().sep_end_by(character(','));
+
+        assert_eq!(Ok((vec![1, 2, 3], "")), parser.parse("1,2,3"));
+        assert_eq!(Ok((vec![1, 2, 3], "")), parser.parse("1,2,3,"));
+        assert_eq!(Ok((vec![1], ",")), parser.parse("1,,"));
+        assert_eq!(Ok((vec![], "")), parser.parse(""));
+        assert_eq!(Ok((vec![], ",")), parser.parse(","));
+    }
+
+    #[test]
+    pub fn test_peek() {
+        let mut parser = character('a').peek();
+
+        let (parsed, rest) = parser.parse("abc").unwrap();
+        assert_eq!(parsed, 'a');
+        assert_eq!(rest, "abc");
+
+        assert!(parser.parse("xyz").is_err());
+    }
+
+    #[test]
+    pub fn test_recognize() {
+        let mut parser = number().recognize();
+
+        let input = "0042x";
+        let (span, rest) = parser.parse(input).unwrap();
+        assert_eq!(span.locate(input), 0..4);
+        assert_eq!(span.slice(input), "0042");
+        assert_eq!(rest, "x");
+
+        let (parsed, rest) = parser.parse_slice(input).unwrap();
+        assert_eq!(parsed, "0042");
+        assert_eq!(parsed.as_ptr(), input.as_ptr());
+        assert_eq!(rest, "x");
+
+        assert!(parser.parse("x").is_err());
+    }
+
+    #[test]
+    pub fn test_value() {
+        let mut parser = character('t').value(true);
+
+        assert_eq!(Ok((true, "")), parser.parse("t"));
+        assert!(parser.parse("f").is_err());
+    }
+
+    #[test]
+    pub fn test_or_else_value() {
+        let mut parser = number().or_else_value(0);
+
+        assert_eq!(Ok((0, "abc")), parser.parse("abc"));
+        assert_eq!(Ok((42, "abc")), parser.parse("42abc"));
+    }
+
+    #[test]
+    pub fn test_verify() {
+        let mut parser = number().verify(|n| *n < 100);
+
+        assert_eq!(Ok((42, "")), parser.parse("42"));
+        assert!(parser.parse("999").is_err());
+        assert_eq!(
+            parse(number().verify(|n| *n < 100), "999")
+                .unwrap_err()
+                .offset(),
+            Some(0)
+        );
+    }
+
+    #[test]
+    pub fn test_spanned() {
+        let mut parser = ident().spanned();
+
+        let ((parsed, span), rest) = parser.parse("foo bar").unwrap();
+        assert_eq!((parsed.as_str(), rest), ("foo", " bar"));
+        assert_eq!(span.locate("foo bar"), 0..3);
+        assert_eq!(span.len(), 3);
+
+        let input = "let foo = 1";
+        let ((_, span), _) = preceded(tag("let "), ident().spanned())
+            .parse(input)
+            .unwrap();
+        assert_eq!(span.locate(input), 4..7);
+        assert_eq!(span.slice(input), "foo");
+
+        let ((_, span), _) = preceded(tag("foo "), ident())
+            .spanned()
+            .parse("foo bar")
+            .unwrap();
+        assert_eq!(span.locate("foo bar"), 0..7);
+        assert!(parser.parse("1").is_err());
+    }
+
+    #[test]
+    pub fn test_map_with_span() {
+        #[derive(Debug, PartialEq)]
+        struct Name {
+            name: String,
+            span: Span,
+        }
+
+        let mut parser = preceded(
+            tag("let "),
+            ident().map_with_span(|name, span| Name { name, span }),
+        );
+        let input = "let foo = 1";
+        let (parsed, rest) = parser.parse(input).unwrap();
+        assert_eq!(parsed.name, "foo");
+        assert_eq!(parsed.span.locate(input), 4..7);
+        assert_eq!(rest, " = 1");
+        assert!(parser.parse("let 1").is_err());
+
+        let mut parser = many(ident().then_ws().map_with_span(|_, span| span.len()));
+        assert_eq!(Ok((vec![3, 4], "")), parser.parse("ab cd  "));
+    }
+
+    #[test]
+    pub fn test_debug() {
+        let mut log = String::new();
+        let mut parser = number().debug_to("number", &mut log);
+
+        assert_eq!(Ok((42, " rest")), parser.parse("42 rest"));
+        assert!(parser.parse("a very long input string").is_err());
+        assert_eq!(
+            log,
+            "number: \"42 rest\" -> ok, remaining \" rest\"\n\
+             number: \"a very long inpu\" -> error\n"
+        );
+
+        #[cfg(feature = "std")]
+        assert_eq!(Ok((1, "")), number().debug("number").parse("1"));
+    }
+
+    #[test]
+    pub fn test_collect() {
+        let mut parser = satisfy(|c| c.is_ascii_alphanumeric()).collect::<String>();
+        assert_eq!(Ok(("abc1".into(), " d")), parser.parse("abc1 d"));
+        assert_eq!(Ok((String::new(), " d")), parser.parse(" d"));
+
+        let mut parser = number()
+            .zip_left(whitespace().optional())
+            .collect::<Vec<_>>();
+        assert_eq!(Ok((vec![1, 2, 3], "x")), parser.parse("1 2 3x"));
+    }
+
+    #[test]
+    pub fn test_iter() {
+        let mut iter = character('a').iter("aaab");
+
+        assert_eq!(Some(Ok('a')), iter.next());
+        assert_eq!(vec![Ok('a'), Ok('a')], iter.by_ref().collect::<Vec<_>>());
+        assert_eq!(None, iter.next());
+        assert_eq!("b", iter.remaining());
+
+        let mut iter = character('a').optional().iter("b");
+        assert!(matches!(iter.next(), Some(Err(_))));
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    pub fn test_clone() {
+        let mut parser = range('0'..='9')
+            .or_same(one_of("+-"))
+            .separated_by(character(','))
+            .zip_left(whitespace().optional())
+            .zip_left(eof());
+        assert_eq!(Ok((vec!['1', '+'], "")), parser.parse("1,+"));
+
+        let mut clone = parser.clone();
+        for input in ["1,+,2 ", "", "1,a"] {
+            assert_eq!(parser.parse(input), clone.parse(input));
+        }
+        assert_eq!(Ok((vec!['-'], "")), clone.parse("-"));
+        assert!(parser.parse("x").is_err());
+    }
+
+    #[test]
+    pub fn test_and() {
+        let mut parser = character('a').and(character('b'));
+
+        assert_eq!(Ok((('a', 'b'), "")), parser.parse("ab"));
+        assert_eq!(Ok((('a', 'b'), "c")), parser.parse("abc"));
+        assert!(parser.parse("a").is_err());
+        assert!(parser.parse("b").is_err());
+    }
+
+    #[test]
+    pub fn test_tuple() {
+        let mut parser = (character('a'), character('b'), character('c'));
+        assert_eq!(Ok((('a', 'b', 'c'), "d")), parser.parse("abcd"));
+        assert!(parser.parse("abx").is_err());
+
+        let mut parser = (character('a'), any());
+        assert_eq!(Ok((('a', 'z'), "")), parser.parse("az"));
+
+        let mut parser = (
+            character('('),
+            number(),
+            whitespace(),
+            number(),
+            whitespace(),
+            character(')'),
+        );
+        assert_eq!(Ok((('(', 1, (), 2, (), ')'), "")), parser.parse("(1 2 )"));
     }
 
     #[test]
@@ -376,7 +2567,7 @@ mod tests {
         let (parsed, rest) = parser.parse("ab").unwrap();
         assert_eq!(parsed, 'a');
         assert_eq!(rest, "");
-        assert_eq!(Err(Error), parser.parse(""));
+        assert!(parser.parse("").is_err());
     }
 
     #[test]
@@ -386,7 +2577,7 @@ mod tests {
         let (parsed, rest) = parser.parse("ab").unwrap();
         assert_eq!(parsed, 'b');
         assert_eq!(rest, "");
-        assert_eq!(Err(Error), parser.parse(""));
+        assert!(parser.parse("").is_err());
     }
 
     #[test]
@@ -399,7 +2590,19 @@ mod tests {
         let (parsed, rest) = parser.parse("ab").unwrap();
         assert_eq!(parsed, 'b');
         assert_eq!(rest, "");
-        assert_eq!(Err(Error), parser.parse(""));
+        assert!(parser.parse("").is_err());
+    }
+
+    #[test]
+    pub fn test_flatten() {
+        let mut parser = character('a').value(character('b')).flatten();
+        assert_eq!(Ok(('b', "c")), parser.parse("abc"));
+        assert!(parser.parse("ac").is_err());
+        assert!(parser.parse("b").is_err());
+
+        let mut parser = any().map(character).flatten();
+        assert_eq!(Ok(('x', "")), parser.parse("xx"));
+        assert!(parser.parse("xy").is_err());
     }
 
     #[test]
@@ -409,7 +2612,48 @@ mod tests {
         let (parsed, rest) = parser.parse("a").unwrap();
         assert_eq!(parsed, 'A');
         assert_eq!(rest, "");
-        assert_eq!(Err(Error), parser.parse(""));
+        assert!(parser.parse("").is_err());
+    }
+
+    #[test]
+    pub fn test_map_err() {
+        let mut calls = 0;
+        let mut parser = character('a').map_err(|_| {
+            calls += 1;
+            Error::new("")
+        });
+
+        assert_eq!(Ok(('a', "b")), parser.parse("ab"));
+        assert_eq!(Err(Error::new("")), parser.parse("ba"));
+        assert_ne!(Err(Error::new("")), character('a').parse("ba"));
+        drop(parser);
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    pub fn test_context() {
+        let mut parser = character('a').context("letter a");
+        assert_eq!(Ok(('a', "")), parser.parse("a"));
+        assert_eq!(parser.parse("b").unwrap_err().label(), Some("letter a"));
+
+        let err = parse(
+            character('(').zip_right(ident().context("identifier")),
+            "(1",
+        )
+        .unwrap_err();
+        assert_eq!(err.label(), Some("identifier"));
+        assert_eq!(
+            err.to_string(),
+            "parse error at byte 1 (line 1, column 2): expected identifier"
+        );
+
+        let err = ident()
+            .context("identifier")
+            .context("form")
+            .parse("1")
+            .unwrap_err();
+        assert_eq!(err.label(), Some("form"));
+        assert_eq!(err.to_string(), "parse error: expected form");
     }
 
     #[test]
@@ -424,18 +2668,360 @@ mod tests {
         assert_eq!(parsed, Either::B('b'));
         assert_eq!(rest, "");
 
-        assert_eq!(Err(Error), parser.parse(""));
+        assert!(parser.parse("").is_err());
+    }
+
+    #[test]
+    pub fn test_or3() {
+        let mut parser = number().or3(character('x'), tag("yz"));
+        assert_eq!(Ok((Either3::A(12), "")), parser.parse("12"));
+        assert_eq!(Ok((Either3::B('x'), "1")), parser.parse("x1"));
+        assert_eq!(Ok((Either3::C("yz"), "")), parser.parse("yz"));
+        assert!(parser.parse("y").is_err());
+
+        let mut parser = lisp_string().or3(lisp_ident(), lisp_list()).map(Get::get);
+        assert_eq!(
+            Ok((LispObject::String("a".into()), "")),
+            parser.parse("\"a\"")
+        );
+        assert_eq!(Ok((LispObject::Ident("a".into()), "")), parser.parse("a"));
+        assert_eq!(Ok((LispObject::List(vec![]), "")), parser.parse("()"));
+    }
+
+    #[test]
+    pub fn test_or4() {
+        let mut parser = number().or4(character('x'), tag("yz"), float());
+        assert_eq!(Ok((Either4::A(1), ".5")), parser.parse("1.5"));
+        assert_eq!(Ok((Either4::B('x'), "")), parser.parse("x"));
+        assert_eq!(Ok((Either4::C("yz"), "")), parser.parse("yz"));
+        assert_eq!(Ok((Either4::D(3e9), "")), parser.parse("3000000000"));
+        assert!(parser.parse("y").is_err());
+
+        let mut parser = character('a')
+            .or4(character('b'), character('c'), character('d'))
+            .map(Get::get);
+        assert_eq!(Ok(('d', "")), parser.parse("d"));
+    }
+
+    #[test]
+    pub fn test_choice() {
+        let mut parser = choice(vec![
+            Box::new(character('a')),
+            Box::new(character('b')),
+            Box::new(character('c')),
+        ]);
+
+        assert_eq!(Ok(('a', "x")), parser.parse("ax"));
+        assert_eq!(Ok(('b', "")), parser.parse("b"));
+        assert_eq!(Ok(('c', "a")), parser.parse("ca"));
+        assert!(parser.parse("d").is_err());
+        assert!(parser.parse("").is_err());
+        assert!(choice::<char>(vec![]).parse("a").is_err());
+    }
+
+    #[test]
+    pub fn test_boxed() {
+        let mut parsers: Vec<Box<dyn Parser<Output = char>>> = vec![
+            character('a').boxed(),
+            any().boxed(),
+            one_of("xyz").boxed(),
+            character('(').zip_right(any()).boxed(),
+        ];
+
+        let results: Vec<_> = parsers.iter_mut().map(|p| p.parse("a")).collect();
+        assert_eq!(
+            results[..3],
+            [
+                Ok(('a', "")),
+                Ok(('a', "")),
+                Err(Error::new("a").with_expected(Expected::OneOf("xyz".into())))
+            ]
+        );
+        assert!(results[3].is_err());
+
+        let (parsed, rest) = parsers[3].parse("(b)").unwrap();
+        assert_eq!((parsed, rest), ('b', ")"));
+
+        let (parsed, rest) = many(character('a').boxed()).parse("aab").unwrap();
+        assert_eq!(parsed, &['a', 'a']);
+        assert_eq!(rest, "b");
+    }
+
+    #[test]
+    pub fn test_tag() {
+        assert_eq!(Ok(("let", "")), Parser::parse(&mut "let", "let"));
+        assert_eq!(Ok(("let", " x")), tag("let").parse("let x"));
+        assert_eq!(Ok(("le", "t")), tag("le").parse("let"));
+        assert!(tag("let").parse("le").is_err());
+        assert!(tag("let").parse("lex").is_err());
+        assert!(tag("let").parse("").is_err());
+
+        let (parsed, rest) = "let".zip_left(whitespace()).parse("let x").unwrap();
+        assert_eq!((parsed, rest), ("let", "x"));
+    }
+
+    #[test]
+    pub fn test_closure() {
+        fn digit(input: &str) -> Result<(char, &str), Error> {
+            range('0'..='9').parse(input)
+        }
+
+        let mut parser = digit.map(|c| c.to_digit(10));
+        assert_eq!(Ok((Some(7), "x")), parser.parse("7x"));
+        assert!(parser.parse("x").is_err());
+
+        fn skip(n: usize) -> impl FnMut(&str) -> Result<((), &str), Error> {
+            move |input| count(n, any()).map(|_| ()).parse(input)
+        }
+
+        let (parsed, rest) = skip(2).zip_right(any()).parse("abcd").unwrap();
+        assert_eq!((parsed, rest), ('c', "d"));
+
+        let (parsed, rest) = many(digit).parse("123x").unwrap();
+        assert_eq!(parsed, &['1', '2', '3']);
+        assert_eq!(rest, "x");
+    }
+
+    #[test]
+    pub fn test_or_same() {
+        let mut parser = character('a').or_same(character('b'));
+
+        let (parsed, rest): (char, _) = parser.parse("ab").unwrap();
+        assert_eq!((parsed, rest), ('a', "b"));
+        assert_eq!(Ok(('b', "")), parser.parse("b"));
+        assert!(parser.parse("c").is_err());
+    }
+
+    #[test]
+    pub fn test_cut() {
+        let list = || character('(').zip_right(number()).zip_left(character(')'));
+
+        let err = parse(list().or_same(number()), "(x)").unwrap_err();
+        assert!(!err.is_cut());
+        assert_eq!(err.offset(), Some(1));
+
+        let mut parser = character('(')
+            .zip_right(number().cut())
+            .zip_left(character(')').cut())
+            .or_same(number());
+        assert_eq!(Ok((1, "")), parser.parse("(1)"));
+        assert_eq!(Ok((2, "")), parser.parse("2"));
+        let err = parser.parse("(x)").unwrap_err().locate("(x)");
+        assert!(err.is_cut());
+        assert_eq!(err.offset(), Some(1));
+        let err = parser.parse("(1").unwrap_err().locate("(1");
+        assert_eq!(err.offset(), Some(2));
+
+        let mut parser = many(character('(').zip_right(number().cut()));
+        assert_eq!(Ok((vec![1, 2], "x")), parser.parse("(1(2x"));
+        assert!(parser.parse("(1(x").unwrap_err().is_cut());
+        assert!(number().cut().optional().parse("x").is_err());
+    }
+
+    #[test]
+    pub fn test_fuse() {
+        use core::cell::Cell;
+
+        let calls = Cell::new(0);
+        let counted = || {
+            from_fn(|input| {
+                calls.set(Cell::get(&calls) + 1);
+                ident().parse(input)
+            })
+        };
+
+        let mut parser = counted()
+            .zip_left(character('!'))
+            .or_same(counted().zip_left(character('?')))
+            .or_same(counted());
+        assert_eq!(Ok(("abc".into(), "")), parser.parse("abc"));
+        assert_eq!(calls.replace(0), 3);
+
+        let ident = counted().fuse();
+        let mut parser = ident
+            .clone()
+            .zip_left(character('!'))
+            .or_same(ident.clone().zip_left(character('?')))
+            .or_same(ident.clone());
+        let input = "abc";
+        assert_eq!(Ok(("abc".into(), "")), parser.parse(input));
+        assert_eq!(calls.replace(0), 1);
+        assert_eq!(Ok(("abc".into(), "")), parser.parse(input));
+        assert_eq!(calls.replace(0), 0);
+        assert_eq!(Ok(("bc".into(), "")), parser.parse(&input[1..]));
+        assert_eq!(calls.replace(0), 1);
+
+        let input = "1";
+        assert!(parser.parse(input).is_err());
+        assert!(parser.parse(input).is_err());
+        assert_eq!(calls.replace(0), 1);
+
+        ident.clear();
+        assert!(parser.parse(input).is_err());
+        assert_eq!(calls.take(), 1);
+
+        let mut digits = take_while1(|c| c.is_ascii_digit()).fuse();
+        let (first, second) = (String::from("12"), String::from("ab"));
+        assert_eq!(Ok(("12".into(), "")), digits.parse(&first));
+        assert!(digits.parse(&second).is_err());
+        assert_eq!(Ok(("12".into(), "")), digits.parse(&first));
+
+        let digit = one_of("0123456789").fuse();
+        let mut buffer = String::from("12x");
+        assert_eq!(Ok(('1', "2x")), parse_partial(digit.clone(), &buffer));
+        buffer.clear();
+        buffer.push_str("ab3");
+        assert!(parse_partial(digit.clone(), &buffer).is_err());
+        buffer.clear();
+        buffer.push_str("4yz");
+        assert_eq!(Ok(('4', "yz")), parse_partial(digit, &buffer));
+
+        buffer.clear();
+        buffer.push_str("56x");
+        assert_eq!(Ok(("56".into(), "x")), digits.parse(&buffer));
+        buffer.clear();
+        buffer.push_str("abc");
+        digits.clear();
+        assert!(digits.parse(&buffer).is_err());
+    }
+
+    #[test]
+    pub fn test_then_ws() {
+        let mut parser = number().then_ws();
+        assert_eq!(Ok((12, "x ")), parser.parse("12 \r\n\tx "));
+        assert_eq!(Ok((12, "")), parser.parse("12"));
+        assert_eq!(Ok((1, "; c")), parser.parse("1 ; c"));
+        assert_eq!(Ok((1, "#| c |#\nx")), parser.parse("1 #| c |#\nx"));
+        assert!(parser.parse(" 12").is_err());
+
+        let mut parser = many(ident().then_ws());
+        assert_eq!(
+            Ok((vec!["a".into(), "b".into()], "")),
+            parser.parse("a  b\n")
+        );
+    }
+
+    #[test]
+    pub fn test_inspect() {
+        let mut count = 0;
+        let mut sum = 0;
+        let mut parser = many(
+            number()
+                .inspect(|n| {
+                    count += 1;
+                    sum += n;
+                })
+                .zip_left(whitespace().optional()),
+        );
+        let input = "1 2 3 x";
+        assert_eq!(parser.parse(input), Ok((vec![1, 2, 3], "x")));
+        drop(parser);
+        assert_eq!((count, sum), (3, 6));
+
+        let mut calls = 0;
+        assert_eq!(
+            number().inspect(|_| calls += 1).parse(input),
+            number().parse(input)
+        );
+        assert!(number().inspect(|_| calls += 1).parse("x").is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    pub fn test_parse_all() {
+        let mut parser = unambiguous(tag("a")).or_same(unambiguous(tag("ab")));
+        assert_eq!(parser.parse_all("abc"), vec![("a", "bc"), ("ab", "c")]);
+        assert_eq!(Ok(("a", "bc")), parser.parse("abc"));
+        assert_eq!(parser.parse_all("ac"), vec![("a", "c")]);
+        assert_eq!(parser.parse_all("x"), vec![]);
+
+        let mut parser = (
+            unambiguous(tag("a")).or_same(unambiguous(tag("ab"))),
+            unambiguous(tag("b")).or_same(unambiguous(tag("bc"))),
+        )
+            .map(|(a, b)| a.len() + b.len());
+        assert_eq!(parser.parse_all("abc"), vec![(2, "c"), (3, "")]);
+        assert_eq!(parser.parse_all("abbc"), vec![(2, "bc"), (3, "c"), (4, "")]);
+
+        let mut parser = unambiguous(character('('))
+            .zip_right(unambiguous(number()).or(unambiguous(tag("1"))))
+            .zip_left(unambiguous(character(')')));
+        assert_eq!(
+            parser.parse_all("(1)"),
+            vec![(Either::A(1), ""), (Either::B("1"), "")]
+        );
+    }
+
+    #[test]
+    pub fn test_or_longest() {
+        let mut parser = number().map(f64::from).or_longest(float());
+        assert_eq!(Ok((1.5, " x")), parser.parse("1.5 x"));
+        assert_eq!(Ok((1.0, "")), parser.parse("1"));
+        assert!(parser.parse("x").is_err());
+
+        let mut parser = tag("ab").or_longest(tag("abc"));
+        assert_eq!(Ok(("abc", "d")), parser.parse("abcd"));
+        assert_eq!(Ok(("ab", "x")), parser.parse("abx"));
+
+        let mut parser = tag("ab").value(1).or_longest(tag("ab").value(2));
+        assert_eq!(Ok((1, "")), parser.parse("ab"));
+    }
+
+    #[test]
+    pub fn test_either() {
+        let a: Either<char, i32> = Either::A('a');
+        let b: Either<char, i32> = Either::B(1);
+
+        assert_eq!(a.clone().left(), Some('a'));
+        assert_eq!(b.clone().left(), None);
+        assert_eq!(a.clone().right(), None);
+        assert_eq!(b.clone().right(), Some(1));
+
+        assert_eq!(
+            a.clone().map_left(|c| c.to_ascii_uppercase()),
+            Either::A('A')
+        );
+        assert_eq!(b.clone().map_left(|c| c.to_ascii_uppercase()), Either::B(1));
+        assert_eq!(a.clone().map_right(|n| n + 1), Either::A('a'));
+        assert_eq!(b.clone().map_right(|n| n + 1), Either::B(2));
+
+        assert_eq!(a.into_result(), Ok('a'));
+        assert_eq!(b.into_result(), Err(1));
     }
 
     #[test]
     pub fn test_character() {
-        assert_eq!(Err(Error), parse(character('2'), "12"));
+        assert!(parse(character('2'), "12").is_err());
 
         let (c, rest) = character('1').parse("12").unwrap();
         assert_eq!(('1', "2"), (c, rest));
         assert_eq!(Ok(('2', "")), character('2').parse(rest));
 
-        assert_eq!(Err(Error), parse(character('2'), ""));
+        assert!(parse(character('2'), "").is_err());
+    }
+
+    #[test]
+    pub fn test_preceded() {
+        let mut parser = preceded(character('-'), any());
+        assert_eq!(Ok(('a', "b")), parser.parse("-ab"));
+        assert!(parser.parse("ab").is_err());
+        assert!(parser.parse("-").is_err());
+    }
+
+    #[test]
+    pub fn test_terminated() {
+        let mut parser = terminated(any(), character(';'));
+        assert_eq!(Ok(('a', "b")), parser.parse("a;b"));
+        assert!(parser.parse("ab").is_err());
+    }
+
+    #[test]
+    pub fn test_delimited() {
+        let mut parser = delimited(character('['), many(one_of("123")), character(']'));
+        assert_eq!(Ok((vec!['1', '2'], "!")), parser.parse("[12]!"));
+        assert_eq!(Ok((vec![], "")), parser.parse("[]"));
+        assert!(parser.parse("[12").is_err());
+        assert!(parser.parse("12]").is_err());
     }
 
     #[test]
@@ -450,6 +3036,110 @@ mod tests {
         assert_eq!(Ok((vec![], "")), many(character('1')).parse(""));
     }
 
+    #[test]
+    pub fn test_many1() {
+        let mut parser = many1(character('a'));
+        let (parsed, rest) = parser.parse("aaab").unwrap();
+        assert_eq!(parsed, &['a'; 3]);
+        assert_eq!(rest, "b");
+
+        assert!(parser.parse("b").is_err());
+        assert!(parser.parse("").is_err());
+    }
+
+    #[test]
+    pub fn test_many_till() {
+        let mut parser = many_till(any(), character('!'));
+
+        assert_eq!(Ok(((vec!['h', 'i'], '!'), "")), parser.parse("hi!"));
+        assert_eq!(Ok(((vec![], '!'), "?")), parser.parse("!?"));
+        assert!(parser.parse("hi").is_err());
+    }
+
+    #[test]
+    pub fn test_fold_many() {
+        let mut parser = fold_many(range('0'..='9').map(|c| c as u8 - b'0'), 0u32, |acc, d| {
+            acc + u32::from(d)
+        });
+
+        assert_eq!(Ok((6, "")), parser.parse("123"));
+        assert_eq!(Ok((9, "x")), parser.parse("45x"));
+        assert_eq!(Ok((0, "x")), parser.parse("x"));
+    }
+
+    #[test]
+    pub fn test_skip_many() {
+        let mut parser = skip_many(whitespace());
+
+        assert_eq!(Ok(((), "x")), parser.parse("   x"));
+        assert_eq!(Ok(((), "x")), parser.parse("x"));
+        assert_eq!(Ok(((), "")), parser.parse(""));
+    }
+
+    #[test]
+    pub fn test_count_matches() {
+        let mut parser = count_matches(character('x'));
+
+        assert_eq!(Ok((3, "y")), parser.parse("xxxy"));
+        assert_eq!(Ok((0, "y")), parser.parse("y"));
+    }
+
+    #[test]
+    pub fn test_count() {
+        let (parsed, rest) = count(3, any()).parse("abcd").unwrap();
+        assert_eq!(parsed, &['a', 'b', 'c']);
+        assert_eq!(rest, "d");
+
+        assert!(count(3, any()).parse("ab").is_err());
+        assert_eq!(Ok((vec![], "ab")), count(0, any()).parse("ab"));
+    }
+
+    #[test]
+    pub fn test_repeat_range() {
+        let mut parser = repeat_range(2..=4, range('0'..='9'));
+
+        assert!(parser.parse("1").is_err());
+        assert!(parser.parse("1x").is_err());
+        assert_eq!(Ok((vec!['1', '2', '3'], "")), parser.parse("123"));
+        assert_eq!(Ok((vec!['1', '2', '3', '4'], "5")), parser.parse("12345"));
+        assert_eq!(Ok((vec![], "")), repeat_range(0..=2, any()).parse(""));
+    }
+
+    #[test]
+    pub fn test_chainl1() {
+        let mut parser = chainl1(number(), character('-').value(|a, b| a - b));
+
+        assert_eq!(Ok((-4, "")), parser.parse("1-2-3"));
+        assert_eq!(Ok((7, "")), parser.parse("7"));
+        assert_eq!(Ok((-1, "-x")), parser.parse("1-2-x"));
+        assert!(parser.parse("-").is_err());
+    }
+
+    #[test]
+    pub fn test_chainr1() {
+        let pow = || character('^').value(|a: i32, b: i32| a.pow(b.unsigned_abs()));
+
+        assert_eq!(Ok((512, "")), chainr1(number(), pow()).parse("2^3^2"));
+        assert_eq!(Ok((64, "")), chainl1(number(), pow()).parse("2^3^2"));
+        assert_eq!(Ok((8, "^")), chainr1(number(), pow()).parse("2^3^"));
+        assert_eq!(Ok((2, "")), chainr1(number(), pow()).parse("2"));
+        assert!(chainr1(number(), pow()).parse("^2").is_err());
+    }
+
+    #[test]
+    pub fn test_not() {
+        let mut parser = not(character(')'));
+        assert_eq!(Ok(((), "abc")), parser.parse("abc"));
+        assert_eq!(Ok(((), "")), parser.parse(""));
+        assert!(parser.parse(")x").is_err());
+
+        let (parsed, rest) = many(not(character(')')).zip_right(any()))
+            .parse("ab)c")
+            .unwrap();
+        assert_eq!(parsed, &['a', 'b']);
+        assert_eq!(rest, ")c");
+    }
+
     #[test]
     pub fn test_any() {
         let input = "()";
@@ -458,7 +3148,7 @@ mod tests {
         assert_eq!(rest, ")");
 
         let input = "";
-        assert_eq!(Err(Error), any().parse(input));
+        assert!(any().parse(input).is_err());
     }
 
     #[test]
@@ -467,6 +3157,7 @@ mod tests {
         let (parsed, rest) = parser.parse("   \n    \tasdf").unwrap();
         assert_eq!(parsed, &[(); 9]);
         assert_eq!(rest, "asdf");
+        assert_eq!(Ok((vec![(); 3], "x")), parser.parse("\r\n\rx"));
         assert_eq!(Ok((vec![], "")), parser.parse(""));
     }
 
@@ -478,7 +3169,64 @@ mod tests {
         assert_eq!(rest, "5");
 
         assert_eq!(Ok((vec![], "")), parser.parse(""));
-        assert_eq!(Err(Error), one_of("").parse("123"));
+        assert!(one_of("").parse("123").is_err());
+    }
+
+    #[test]
+    pub fn test_multibyte() {
+        assert_eq!(Ok(('é', "")), character('é').parse("é"));
+        assert_eq!(Ok(('你', "好")), character('你').parse("你好"));
+        assert!(character('e').parse("é").is_err());
+        assert_eq!(Ok(('你', "好")), any().parse("你好"));
+        assert_eq!(Ok(('é', "t")), one_of("àé").parse("ét"));
+        assert!(one_of("e").parse("é").is_err());
+        assert_eq!(
+            Ok(('你', "好")),
+            range('\u{4e00}'..='\u{9fff}').parse("你好")
+        );
+        assert!(range('a'..='z').parse("é").is_err());
+        assert_eq!(Ok((vec!['你', '好'], "")), many(any()).parse("你好"));
+        assert_eq!(
+            Ok(("abé1你".into(), " x")),
+            take_while(char::is_alphanumeric).parse("abé1你 x")
+        );
+        assert_eq!(
+            Ok(("ab".into(), "é")),
+            take_while(|c| c.is_ascii()).parse("abé")
+        );
+    }
+
+    #[test]
+    pub fn test_none_of() {
+        let mut parser = many(none_of("\"\\"));
+        let (parsed, rest) = parser.parse("ab\"c").unwrap();
+        assert_eq!(parsed, &['a', 'b']);
+        assert_eq!(rest, "\"c");
+
+        assert_eq!(Ok(('a', "")), none_of("\"\\").parse("a"));
+        assert_eq!(Ok(('é', "x")), none_of("\"\\").parse("éx"));
+        assert!(none_of("\"\\").parse("\"").is_err());
+        assert!(none_of("\"\\").parse("\\").is_err());
+        assert!(none_of("\"\\").parse("").is_err());
+        assert_eq!(Ok(('1', "23")), none_of("").parse("123"));
+    }
+
+    #[test]
+    pub fn test_satisfy() {
+        let mut parser = satisfy(|c| c.is_ascii_uppercase());
+        assert_eq!(Ok(('A', "bc")), parser.parse("Abc"));
+        assert!(parser.parse("abc").is_err());
+        assert!(parser.parse("").is_err());
+    }
+
+    #[test]
+    pub fn test_eof() {
+        assert_eq!(Ok(((), "")), eof().parse(""));
+        assert!(eof().parse("x").is_err());
+
+        let mut parser = character('a').zip_left(eof());
+        assert_eq!(Ok(('a', "")), parser.parse("a"));
+        assert!(parser.parse("ab").is_err());
     }
 
     #[test]
@@ -489,6 +3237,200 @@ mod tests {
         assert_eq!(rest, "!");
 
         assert_eq!(Ok((vec![], "")), parser.parse(""));
-        assert_eq!(Err(Error), range('a'..='a').parse("123"));
+        assert!(range('a'..='a').parse("123").is_err());
+    }
+
+    #[test]
+    pub fn test_error_position() {
+        let err = parse(many(character('a')).zip_left(character('b')), "aaac").unwrap_err();
+        assert_eq!(err.offset(), Some(3));
+        assert_eq!(
+            err.position(),
+            Some(Position {
+                offset: 3,
+                line: 1,
+                column: 4
+            })
+        );
+
+        let err = parse(many(any().zip_left(character('\n'))), "a\nb\ncd").unwrap_err();
+        assert_eq!(
+            err.position(),
+            Some(Position {
+                offset: 4,
+                line: 3,
+                column: 1
+            })
+        );
+
+        let err = character('a').parse("b").unwrap_err();
+        assert_eq!(err.position(), None);
+        assert_eq!(err.locate("ab").offset(), Some(1));
+    }
+
+    #[test]
+    pub fn test_parse_partial() {
+        let (parsed, rest) = parse_partial(lisp_object(), "(a) (b)").unwrap();
+        assert_eq!(parsed, "(a)".parse().unwrap());
+        assert_eq!(rest, "(b)");
+
+        assert_eq!(Ok((1, "")), parse_partial(number(), "1"));
+        let err = parse_partial(lisp_object(), "(a (b").unwrap_err();
+        assert_eq!(err.offset(), Some(5));
+    }
+
+    #[test]
+    pub fn test_error_expected() {
+        let err = parse(preceded(character('('), character(')')), "(x").unwrap_err();
+        assert_eq!(err.expected(), Some(&Expected::Char(')')));
+        assert_eq!(err.offset(), Some(1));
+        assert_eq!(
+            err.to_string(),
+            "parse error at byte 1 (line 1, column 2): expected ')'"
+        );
+
+        let err = one_of("123").parse("x").unwrap_err();
+        assert_eq!(err.expected().unwrap().to_string(), r#"one of "123""#);
+        let err = range('a'..='z').parse("").unwrap_err();
+        assert_eq!(err.expected().unwrap().to_string(), "'a'..='z'");
+        let err = satisfy(char::is_numeric).parse("x").unwrap_err();
+        assert_eq!(err.expected(), Some(&Expected::Satisfy));
+        let err = none_of("()").parse(")").unwrap_err();
+        assert_eq!(err.expected().unwrap().to_string(), r#"none of "()""#);
+        assert_eq!(Error::new("x").expected(), None);
+    }
+
+    #[test]
+    pub fn test_error_expected_alternatives() {
+        let err = parse(character('a').or(one_of("xy")), "b").unwrap_err();
+        assert_eq!(
+            err.expected(),
+            Some(&Expected::Alternatives(vec![
+                Expected::Char('a'),
+                Expected::OneOf("xy".into())
+            ]))
+        );
+        assert_eq!(
+            err.to_string(),
+            r#"parse error at byte 0 (line 1, column 1): expected 'a' or one of "xy""#
+        );
+
+        let err = parse(
+            character('a')
+                .or_same(character('b'))
+                .or_same(character('a')),
+            "c",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "parse error at byte 0 (line 1, column 1): expected 'a' or 'b'"
+        );
+
+        let err = parse(
+            choice(vec![
+                character('a').zip_right(character('b')).boxed(),
+                character('c').boxed(),
+            ]),
+            "ax",
+        )
+        .unwrap_err();
+        assert_eq!(err.offset(), Some(1));
+        assert_eq!(err.expected(), Some(&Expected::Char('b')));
+
+        let err = parse(character('-').or3(eof(), none_of("x")), "x").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"parse error at byte 0 (line 1, column 1): expected '-' or end of input or none of "x""#
+        );
+
+        let err = parse(
+            character('a')
+                .peek()
+                .zip_right(any())
+                .or_same(character('b')),
+            "c",
+        )
+        .unwrap_err();
+        assert_eq!(err.expected(), Some(&Expected::Char('b')));
+
+        let err = parse(lisp_object(), "(a b").unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+        assert!(err.to_string().contains("expected ')' or"), "{err}");
+        let err = parse(lisp_object(), "(a #\\spacex)").unwrap_err();
+        assert_eq!(err.offset(), Some(5));
+        assert_eq!(err.label(), Some("a character name"));
+    }
+
+    #[test]
+    pub fn test_error_display() {
+        let err = parse(character('a'), "ab").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "parse error at byte 1 (line 1, column 2): expected end of input"
+        );
+
+        let err = character('a').parse("b").unwrap_err();
+        assert_eq!(err.to_string(), "parse error: expected 'a'");
+
+        let boxed: Box<dyn std::error::Error> = Box::new(err);
+        assert_eq!(boxed.to_string(), "parse error: expected 'a'");
+
+        let err = Error::new("").with_expected(Expected::Satisfy);
+        assert_eq!(
+            err.to_string(),
+            "parse error: expected a matching character"
+        );
+        let err = err.with_label("digit");
+        assert_eq!(err.to_string(), "parse error: expected digit");
+    }
+
+    #[test]
+    pub fn test_take_while() {
+        let mut parser = take_while(char::is_alphabetic);
+        assert_eq!(Ok(("abc".into(), "123")), parser.parse("abc123"));
+        assert_eq!(Ok(("".into(), "123abc")), parser.parse("123abc"));
+        assert_eq!(Ok(("".into(), "")), parser.parse(""));
+    }
+
+    #[test]
+    pub fn test_take_while1() {
+        let mut parser = take_while1(char::is_alphabetic);
+        assert_eq!(Ok(("abc".into(), "123")), parser.parse("abc123"));
+        assert!(parser.parse("123abc").is_err());
+        assert!(parser.parse("").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    pub fn test_parse_reader() {
+        use crate::{lisp_comb::lisp_object, LispObject::*};
+
+        struct Chunked<'a>(&'a [u8]);
+
+        impl io::Read for Chunked<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = self.0.len().min(buf.len()).min(3);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let parsed = parse_reader(lisp_object(), Chunked(r#"(a ("str" b) c)"#.as_bytes())).unwrap();
+        assert_eq!(
+            parsed,
+            List(vec![
+                Ident("a".into()),
+                List(vec![String("str".into()), Ident("b".into())]),
+                Ident("c".into()),
+            ])
+        );
+
+        let err = parse_reader(lisp_object(), Chunked(b"(a b")).unwrap_err();
+        assert!(matches!(err, ReadError::Parse(_)));
+
+        let err = parse_reader(lisp_object(), Chunked(b"(a \xff)")).unwrap_err();
+        assert!(matches!(err, ReadError::Io(_)));
     }
 }